@@ -1,12 +1,28 @@
-use fractals::{draw, rgb::RGBRenderer, Generator, Grid, Palette, Point};
+use crate::text;
+use fractals::{
+    color_escapes, draw,
+    export::PngRenderer,
+    julia::Julia,
+    rgb::{heatmap_escapes, smooth_color_escapes, Blending, RGBRenderer},
+    sample_escapes,
+    view::{Bounds, View, Viewport},
+    BoxedGenerator, EscapeResult, Generator, Grid, Palette, Point, Rect, Renderer, SamplePattern,
+};
 
 use pixel_canvas::{
     canvas::CanvasInfo,
     input::{Event, WindowEvent},
     Color, Image, XY,
 };
-use std::cell::RefCell;
-use winit::event::{ElementState, MouseButton};
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+use winit::event::{ElementState, KeyboardInput, MouseButton, VirtualKeyCode};
 
 #[derive(Copy, Clone, Debug)]
 struct Position {
@@ -38,24 +54,804 @@ struct DraggingState {
 struct CanvasDims {
     min: Point,
     max: Point,
+    // how many degrees the view is turned anticlockwise about its center
+    rotation: f64,
 }
 
+impl CanvasDims {
+    fn viewport(&self, width: usize, height: usize) -> Viewport {
+        Viewport {
+            min: self.min,
+            max: self.max,
+            width,
+            height,
+        }
+    }
+
+    fn center(&self) -> Point {
+        Bounds {
+            min: self.min,
+            max: self.max,
+        }
+        .center()
+    }
+
+    // where `p` of the upright view between the corners is once the view is turned
+    fn turned(&self, p: Point) -> Point {
+        p.rotated_about(self.center(), self.rotation.to_radians())
+    }
+
+    // the column and row of the image where `p` lands, which may be off the image
+    fn pixel_of(&self, p: Point, image: &Image) -> (f64, f64) {
+        let upright = p.rotated_about(self.center(), -self.rotation.to_radians());
+        self.viewport(image.width(), image.height())
+            .point_to_pixel(upright)
+    }
+
+    // the view the same size and turned as far, moved to be centred on `center`
+    fn recentered(&self, center: Point) -> Self {
+        let (half_width, half_height) = self.half_extents();
+        let Bounds { min, max } = Bounds::new_centered(center, half_width, half_height);
+        Self {
+            min,
+            max,
+            rotation: self.rotation,
+        }
+    }
+
+    fn half_extents(&self) -> (f64, f64) {
+        Bounds {
+            min: self.min,
+            max: self.max,
+        }
+        .half_extents()
+    }
+
+    // maps a pixel position on the canvas to its coordinate in the complex plane
+    fn point_at(&self, info: &CanvasInfo, x: i32, y: i32) -> Point {
+        let upright = self
+            .viewport(info.width, info.height)
+            .pixel_to_point(f64::from(x), f64::from(y));
+        self.turned(upright)
+    }
+
+    // the points sampled for a `width` by `height` image of the view
+    fn grid(&self, width: usize, height: usize) -> Grid<Point> {
+        Grid::new(width, height, self.min, self.max)
+            .rotated_about(self.center(), self.rotation.to_radians())
+    }
+}
+
+// colors per second the palette cycles at, to start with and at the slowest and fastest
+const DEFAULT_CYCLE_SPEED: f64 = 8.0;
+const MIN_CYCLE_SPEED: f64 = 0.5;
+const MAX_CYCLE_SPEED: f64 = 128.0;
+
+// how many rows of pixels an export follows the orbits of at once
+const EXPORT_BAND_ROWS: usize = 64;
+
+// how many finished renders to keep unless told otherwise, at about 20MB each for the
+// escape of every pixel
+const DEFAULT_CACHE_CAPACITY: usize = 4;
+
+// two left presses closer together than this are treated as a double-click
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+
+// how many degrees `,` and `.` turn the view by
+const ROTATION_STEP: f64 = 5.0;
+
+// how long the name of a palette switched to with `O` stays on screen
+const PALETTE_LABEL_TIME: Duration = Duration::from_secs(2);
+
 enum RenderState {
     Dragging(DraggingState),
     Recalc(Point, Point),
     Done(Point, Point, Position, Image),
 }
 
+// Everything that decides how the orbits of a view escape, compared bit for bit
+#[derive(Copy, Clone, PartialEq, Eq)]
+struct RenderKey {
+    fractal: usize,
+    bounds: [u64; 4],
+    rotation: u64,
+    width: usize,
+    height: usize,
+    max_iter: usize,
+}
+
+impl RenderKey {
+    fn new(fractal: usize, dims: CanvasDims, image: &Image, max_iter: usize) -> Self {
+        Self {
+            fractal,
+            bounds: [
+                dims.min.0.to_bits(),
+                dims.min.1.to_bits(),
+                dims.max.0.to_bits(),
+                dims.max.1.to_bits(),
+            ],
+            rotation: dims.rotation.to_bits(),
+            width: image.width(),
+            height: image.height(),
+            max_iter,
+        }
+    }
+}
+
+// How the orbits of the most recently used views escaped, so that going back to a view doesn't
+// sample it again, whatever it's colored with now
+struct RenderCache {
+    capacity: usize,
+    // least recently used first
+    renders: VecDeque<(RenderKey, Grid<EscapeResult>)>,
+}
+
+impl RenderCache {
+    fn get(&mut self, key: RenderKey) -> Option<&Grid<EscapeResult>> {
+        let index = self.renders.iter().position(|(k, _)| *k == key)?;
+        let render = self.renders.remove(index)?;
+        self.renders.push_back(render);
+        self.renders.back().map(|(_, escapes)| escapes)
+    }
+
+    fn insert(&mut self, key: RenderKey, escapes: &Grid<EscapeResult>) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.renders.retain(|(k, _)| *k != key);
+        while self.renders.len() >= self.capacity {
+            self.renders.pop_front();
+        }
+        self.renders.push_back((key, escapes.clone()));
+    }
+}
+
+// What a finished view's colors depend on besides how its orbits escaped
+#[derive(Copy, Clone, PartialEq)]
+struct Coloring {
+    palette: usize,
+    heatmap: bool,
+    smooth: bool,
+    cycling: bool,
+}
+
+// How `E` renders the view it saves
+struct ExportSettings {
+    scale: usize,
+    ssaa: usize,
+    pattern: SamplePattern,
+    blending: Blending,
+}
+
+// Where an export asked for with `E` has got to. The label goes up a frame before the slow
+// render so that it's on screen while the render runs.
+#[derive(Copy, Clone, PartialEq)]
+enum Export {
+    Idle,
+    Requested,
+    Labelled,
+}
+
+// Julia set for the point under the cursor, drawn as an inset over the view
+struct JuliaPreview {
+    visible: bool,
+    c: Point,
+}
+
+struct Fractal {
+    key: Option<VirtualKeyCode>,
+    generator: BoxedGenerator,
+    julia_preview: bool,
+    // the command line arguments that pick this fractal
+    args: Vec<String>,
+    // the power z is raised to each step, which smooth coloring needs to make the bands meet
+    power: f64,
+}
+
 pub struct CanvasState {
     initial_dims: CanvasDims,
     render_state: RefCell<RenderState>,
+    last_click: Option<Instant>,
+    // where the cursor last was, which each finished render starts from so that the crosshair
+    // and the next click stay under it
+    cursor: Position,
+    fractals: Vec<Fractal>,
+    fractal: usize,
+    // how many degrees the view is turned anticlockwise, which the corners of each view are
+    // turned by about their middle
+    rotation: f64,
+    // how many times wider than tall the pixels are, which the views printed and bookmarked
+    // are narrowed by so that they're framed for square ones like --bounds
+    pixel_aspect: f64,
+    julia_preview: JuliaPreview,
+    show_render_time: bool,
+    last_render: Duration,
+    show_axes: bool,
+    show_minimap: bool,
+    show_crosshair: bool,
+    // whether zoom selections keep to the canvas's aspect ratio
+    fixed_aspect: bool,
+    // animate the palette along the escape counts, at this many colors per second
+    cycling: bool,
+    cycle_speed: f64,
+    cycle_start: Instant,
+    // color by how many iterations each point cost instead of with the palette
+    show_heatmap: bool,
+    // blend between the palette's colors instead of showing bands of them
+    smooth: bool,
+    // the names of the palettes `O` cycles through, which the render callback is given in the
+    // same order, and the one in use
+    palette_names: Vec<&'static str>,
+    palette: usize,
+    // how the finished image was colored, as changing that recolors it instead
+    drawn: Coloring,
+    // when the palette was last switched, while its name is still shown
+    palette_switched: Option<Instant>,
+    // how the orbits of the view on screen ended, so that recoloring it doesn't sample it
+    // again and panning only has to sample what's new
+    escapes: Option<(CanvasDims, Grid<EscapeResult>)>,
+    // the initial view of the current fractal, rendered small
+    minimap: Option<Image>,
+    bookmarks: Vec<CanvasDims>,
+    next_bookmark: usize,
+    bookmark_file: Option<PathBuf>,
+    // the program and the flags every fractal shares, for printing the current view as a command
+    command: Vec<String>,
+    cache: RenderCache,
+    export_settings: ExportSettings,
+    export: Export,
+    // the finished image needs to be copied to the canvas and overlays redrawn
+    frame_stale: bool,
 }
 
 impl CanvasState {
-    pub fn new(min: Point, max: Point) -> Self {
+    pub fn new(min: Point, max: Point, generator: BoxedGenerator) -> Self {
         Self {
-            initial_dims: CanvasDims { min, max },
+            initial_dims: CanvasDims {
+                min,
+                max,
+                rotation: 0.0,
+            },
             render_state: RefCell::new(RenderState::Recalc(min, max)),
+            last_click: None,
+            cursor: Position::new(),
+            fractals: vec![Fractal {
+                key: None,
+                generator,
+                julia_preview: false,
+                args: vec![],
+                power: 2.0,
+            }],
+            fractal: 0,
+            rotation: 0.0,
+            pixel_aspect: 1.0,
+            julia_preview: JuliaPreview {
+                visible: false,
+                c: Point(0.0, 0.0),
+            },
+            show_render_time: false,
+            last_render: Duration::from_secs(0),
+            show_axes: false,
+            show_minimap: true,
+            show_crosshair: false,
+            fixed_aspect: true,
+            cycling: false,
+            cycle_speed: DEFAULT_CYCLE_SPEED,
+            cycle_start: Instant::now(),
+            show_heatmap: false,
+            smooth: false,
+            palette_names: vec![],
+            palette: 0,
+            drawn: Coloring {
+                palette: 0,
+                heatmap: false,
+                smooth: false,
+                cycling: false,
+            },
+            palette_switched: None,
+            escapes: None,
+            minimap: None,
+            bookmarks: vec![],
+            next_bookmark: 0,
+            bookmark_file: None,
+            command: vec!["fractals".to_owned()],
+            cache: RenderCache {
+                capacity: DEFAULT_CACHE_CAPACITY,
+                renders: VecDeque::new(),
+            },
+            export_settings: ExportSettings {
+                scale: 2,
+                ssaa: 2,
+                pattern: SamplePattern::RotatedGrid,
+                blending: Blending::Gamma,
+            },
+            export: Export::Idle,
+            frame_stale: false,
+        }
+    }
+
+    /// Switches to `generator` at the current bounds when `key` is pressed.
+    pub fn with_fractal(mut self, key: VirtualKeyCode, generator: BoxedGenerator) -> Self {
+        self.fractals.push(Fractal {
+            key: Some(key),
+            generator,
+            julia_preview: false,
+            args: vec![],
+            power: 2.0,
+        });
+        self
+    }
+
+    /// Makes `key` switch to the most recently added fractal, for the one the canvas starts
+    /// on to have a key of its own.
+    pub fn with_key(mut self, key: VirtualKeyCode) -> Self {
+        self.fractals.last_mut().unwrap().key = Some(key);
+        self
+    }
+
+    /// Allows toggling a live Julia set preview for the point under the cursor with `H`
+    /// while the most recently added fractal is shown.
+    pub fn with_julia_preview(mut self) -> Self {
+        self.fractals.last_mut().unwrap().julia_preview = true;
+        self
+    }
+
+    /// Sets the command line arguments that select the most recently added fractal, for the
+    /// command printed with `P`.
+    pub fn with_args(mut self, args: Vec<String>) -> Self {
+        self.fractals.last_mut().unwrap().args = args;
+        self
+    }
+
+    /// Sets the power z is raised to each step of the most recently added fractal, which `S`
+    /// needs for smooth colors without seams. It's 2 unless set.
+    pub fn with_power(mut self, power: f64) -> Self {
+        self.fractals.last_mut().unwrap().power = power;
+        self
+    }
+
+    /// Starts with smooth colors instead of bands when `smooth` is true. `S` switches between
+    /// them either way.
+    pub fn with_smooth_coloring(self, smooth: bool) -> Self {
+        Self { smooth, ..self }
+    }
+
+    /// Lets `O` and `Shift+O` cycle forwards and backwards through the palettes called
+    /// `names`, starting at `names[current]`. The render callback has to be given the
+    /// palettes themselves in the same order.
+    pub fn with_palettes(self, names: Vec<&'static str>, current: usize) -> Self {
+        assert!(current < names.len());
+        Self {
+            palette_names: names,
+            palette: current,
+            ..self
+        }
+    }
+
+    /// Starts with the view turned `degrees` anticlockwise about its center, which is where
+    /// right-clicking goes back to as well. `,` and `.` turn it further.
+    pub fn with_rotation(mut self, degrees: f64) -> Self {
+        self.initial_dims.rotation = degrees;
+        self.rotation = degrees;
+        self
+    }
+
+    /// Renders for pixels `aspect` times as wide as they are tall, as `--pixel-aspect` does, so
+    /// that `P` and bookmarks give the current view as it would be framed for square pixels and
+    /// bookmarks are stretched again when jumped to. It's 1 unless set.
+    pub fn with_pixel_aspect(self, aspect: f64) -> Self {
+        assert!(aspect > 0.0);
+        Self {
+            pixel_aspect: aspect,
+            ..self
+        }
+    }
+
+    /// Sets the program and the flags every fractal shares, which `P` prints before the bounds
+    /// and the current fractal's own arguments to give a command that renders the same view.
+    pub fn with_command(self, command: Vec<String>) -> Self {
+        Self { command, ..self }
+    }
+
+    /// Keeps up to `capacity` finished renders to show again without sampling when their view
+    /// comes back, as it does with bookmarks and switching fractals. 0 turns the cache off.
+    pub fn with_cache_capacity(mut self, capacity: usize) -> Self {
+        self.cache = RenderCache {
+            capacity,
+            renders: VecDeque::new(),
+        };
+        self
+    }
+
+    /// Makes `E` save the view at `scale` times the window's size, supersampled by `ssaa` with
+    /// its points in `pattern` and averaged with `blending`. The default is twice the size with a
+    /// 2 by 2 rotated grid.
+    pub fn with_export(
+        self,
+        scale: usize,
+        ssaa: usize,
+        pattern: SamplePattern,
+        blending: Blending,
+    ) -> Self {
+        assert!(scale > 0 && ssaa > 0);
+        Self {
+            export_settings: ExportSettings {
+                scale,
+                ssaa,
+                pattern,
+                blending,
+            },
+            ..self
+        }
+    }
+
+    /// Starts with `bookmarks` to cycle through, and writes every bookmark made to `file`.
+    pub fn with_bookmarks(self, bookmarks: Vec<View>, file: Option<PathBuf>) -> Self {
+        Self {
+            bookmarks: bookmarks
+                .into_iter()
+                .map(
+                    |View {
+                         bounds, rotation, ..
+                     }| CanvasDims {
+                        min: bounds.min,
+                        max: bounds.max,
+                        rotation,
+                    },
+                )
+                .collect(),
+            bookmark_file: file,
+            ..self
+        }
+    }
+
+    fn print_command(&self, min: Point, max: Point) {
+        let square = Bounds { min, max }.with_pixel_aspect(1.0 / self.pixel_aspect);
+        let bounds = format!("--bounds={}", square);
+        let rotate = format!("--rotate={}", self.rotation);
+        let palette = self
+            .palette_names
+            .get(self.palette)
+            .map(|name| format!("--palette={}", name));
+        let args = &self.fractals[self.fractal].args;
+        let command: Vec<_> = self
+            .command
+            .iter()
+            .chain(Some(&bounds))
+            .chain((self.rotation != 0.0).then_some(&rotate))
+            .chain(&palette)
+            .chain(args)
+            .map(String::as_str)
+            .collect();
+        println!("{}", command.join(" "));
+    }
+
+    fn save_bookmarks(&self) {
+        let path = match &self.bookmark_file {
+            Some(path) => path,
+            None => return,
+        };
+        let views: Vec<_> = self
+            .bookmarks
+            .iter()
+            .map(|dims| {
+                View::new(Bounds {
+                    min: dims.min,
+                    max: dims.max,
+                })
+                .with_rotation(dims.rotation)
+            })
+            .collect();
+        let result = File::create(path)
+            .map_err(|e| e.to_string())
+            .and_then(|file| {
+                serde_json::to_writer_pretty(BufWriter::new(file), &views)
+                    .map_err(|e| e.to_string())
+            });
+        if let Err(e) = result {
+            eprintln!("couldn't save bookmarks to {}: {}", path.display(), e);
+        }
+    }
+
+    fn has_julia_preview(&self) -> bool {
+        self.fractals[self.fractal].julia_preview
+    }
+
+    fn draw_minimap(&self, image: &mut Image, minimap: &Image, dims: CanvasDims) {
+        let (left, bottom) = (
+            image.width() - MINIMAP_WIDTH - OVERLAY_MARGIN,
+            OVERLAY_MARGIN,
+        );
+        for (y, row) in minimap.chunks(MINIMAP_WIDTH).enumerate() {
+            for (x, pixel) in row.iter().enumerate() {
+                image[XY(left + x, bottom + y)] = *pixel;
+            }
+        }
+        draw_box(
+            image,
+            (left - 1, bottom - 1),
+            (left + MINIMAP_WIDTH, bottom + MINIMAP_HEIGHT),
+            Color::WHITE,
+        );
+
+        // outline the current view, keeping it visible however deep the zoom is, and boxing
+        // all four of its corners when it's turned
+        let corners = [
+            dims.min,
+            dims.max,
+            Point(dims.min.0, dims.max.1),
+            Point(dims.max.0, dims.min.1),
+        ]
+        .map(|corner| self.initial_dims.pixel_of(dims.turned(corner), minimap));
+        let (min_x, max_x, min_y, max_y) = corners.iter().fold(
+            (
+                f64::INFINITY,
+                f64::NEG_INFINITY,
+                f64::INFINITY,
+                f64::NEG_INFINITY,
+            ),
+            |(min_x, max_x, min_y, max_y), &(x, y)| {
+                (min_x.min(x), max_x.max(x), min_y.min(y), max_y.max(y))
+            },
+        );
+        let clamp = |v: f64, size: usize| v.max(0.0).min(size as f64 - 1.0) as usize;
+        let (min_x, max_x) = (clamp(min_x, MINIMAP_WIDTH), clamp(max_x, MINIMAP_WIDTH));
+        let (min_y, max_y) = (clamp(min_y, MINIMAP_HEIGHT), clamp(max_y, MINIMAP_HEIGHT));
+        draw_box(
+            image,
+            (left + min_x, bottom + min_y),
+            (left + max_x.max(min_x + 2), bottom + max_y.max(min_y + 2)),
+            Color::rgb(0, 255, 0),
+        );
+    }
+
+    fn handle_key(&mut self, key: VirtualKeyCode, shift: bool) -> bool {
+        match key {
+            // toggle the Julia preview
+            VirtualKeyCode::H if self.has_julia_preview() => {
+                self.julia_preview.visible = !self.julia_preview.visible;
+            }
+            // toggle the render time display
+            VirtualKeyCode::T => self.show_render_time = !self.show_render_time,
+            // toggle the axes and unit circle
+            VirtualKeyCode::A => self.show_axes = !self.show_axes,
+            // toggle the minimap
+            VirtualKeyCode::N => self.show_minimap = !self.show_minimap,
+            // toggle the crosshair and its coordinate
+            VirtualKeyCode::X => self.show_crosshair = !self.show_crosshair,
+            // start or stop cycling the colors
+            VirtualKeyCode::C => {
+                self.cycling = !self.cycling;
+                self.cycle_start = Instant::now();
+            }
+            // toggle coloring by iteration depth
+            VirtualKeyCode::I => self.show_heatmap = !self.show_heatmap,
+            // switch between bands of color and smooth blends
+            VirtualKeyCode::S => self.smooth = !self.smooth,
+            // slow down or speed up the cycling
+            VirtualKeyCode::LBracket => {
+                self.cycle_speed = (self.cycle_speed / 2.0).max(MIN_CYCLE_SPEED);
+                return false;
+            }
+            VirtualKeyCode::RBracket => {
+                self.cycle_speed = (self.cycle_speed * 2.0).min(MAX_CYCLE_SPEED);
+                return false;
+            }
+            // switch between free and fixed aspect zoom selections
+            VirtualKeyCode::F => {
+                self.fixed_aspect = !self.fixed_aspect;
+                return false;
+            }
+            // bookmark the current view
+            VirtualKeyCode::K => {
+                if let RenderState::Done(min, max, ..) = self.render_state.get_mut() {
+                    let Bounds { min, max } = Bounds {
+                        min: *min,
+                        max: *max,
+                    }
+                    .with_pixel_aspect(1.0 / self.pixel_aspect);
+                    self.bookmarks.push(CanvasDims {
+                        min,
+                        max,
+                        rotation: self.rotation,
+                    });
+                    self.save_bookmarks();
+                }
+                return false;
+            }
+            // print a command that renders the current view
+            VirtualKeyCode::P => {
+                if let RenderState::Done(min, max, ..) = self.render_state.get_mut() {
+                    let (min, max) = (*min, *max);
+                    self.print_command(min, max);
+                }
+                return false;
+            }
+            // recolor the view with the next or previous palette, keeping how its orbits
+            // escaped
+            VirtualKeyCode::O if self.palette_names.len() > 1 => {
+                let count = self.palette_names.len();
+                self.palette = if shift {
+                    (self.palette + count - 1) % count
+                } else {
+                    (self.palette + 1) % count
+                };
+                self.palette_switched = Some(Instant::now());
+                // the minimap can't be reused in the old colors
+                self.minimap = None;
+            }
+            // turn the view a step anticlockwise or clockwise
+            VirtualKeyCode::Comma | VirtualKeyCode::Period => {
+                if let RenderState::Done(min, max, ..) = self.render_state.get_mut() {
+                    let (min, max) = (*min, *max);
+                    let step = if key == VirtualKeyCode::Comma {
+                        ROTATION_STEP
+                    } else {
+                        -ROTATION_STEP
+                    };
+                    self.rotation = (self.rotation + step) % 360.0;
+                    self.render_state = RefCell::new(RenderState::Recalc(min, max));
+                    return true;
+                }
+                return false;
+            }
+            // save the current view at export quality
+            VirtualKeyCode::E => {
+                if let RenderState::Done(..) = self.render_state.get_mut() {
+                    self.export = Export::Requested;
+                }
+            }
+            // jump to the next bookmark
+            VirtualKeyCode::Tab => {
+                if let (false, RenderState::Done(..)) =
+                    (self.bookmarks.is_empty(), self.render_state.get_mut())
+                {
+                    let dims = self.bookmarks[self.next_bookmark % self.bookmarks.len()];
+                    self.next_bookmark = (self.next_bookmark + 1) % self.bookmarks.len();
+                    self.rotation = dims.rotation;
+                    let Bounds { min, max } = Bounds {
+                        min: dims.min,
+                        max: dims.max,
+                    }
+                    .with_pixel_aspect(self.pixel_aspect);
+                    self.render_state = RefCell::new(RenderState::Recalc(min, max));
+                    return true;
+                }
+                return false;
+            }
+            // switch fractals, keeping the current bounds
+            _ => {
+                let fractal = self.fractals.iter().position(|f| f.key == Some(key));
+                return match (fractal, self.render_state.get_mut()) {
+                    (Some(fractal), RenderState::Done(min, max, ..)) if fractal != self.fractal => {
+                        self.fractal = fractal;
+                        self.minimap = None;
+                        self.escapes = None;
+                        self.render_state = RefCell::new(RenderState::Recalc(*min, *max));
+                        true
+                    }
+                    _ => false,
+                };
+            }
+        }
+        self.frame_stale = true;
+        true
+    }
+
+    // renders the view between `min` and `max` again as `E` exports it, colored the way it is
+    // on screen, and saves it to the first file named like export-1.png that doesn't exist yet.
+    // The orbits are followed a band of rows at a time, so only the colors are kept for all of
+    // it.
+    fn export<P: Palette<Item = Color> + Sync>(
+        &self,
+        palette: &P,
+        max_iter: usize,
+        min: Point,
+        max: Point,
+    ) {
+        let ExportSettings {
+            scale,
+            ssaa,
+            pattern,
+            blending,
+        } = self.export_settings;
+        let (width, height) = (RGB_WIDTH * scale, RGB_HEIGHT * scale);
+        let center = Bounds { min, max }.center();
+        let grid = Grid::supersampled_in_pattern(width, height, min, max, ssaa, pattern)
+            .rotated_about(center, self.rotation.to_radians());
+        let generator = &self.fractals[self.fractal].generator;
+        // cycling colors keep moving while it renders, so every band is drawn where they were
+        // when E was pressed
+        let shift = self.cycle_shift();
+        let mut rows = Vec::with_capacity(height);
+        for band in grid.0.chunks(EXPORT_BAND_ROWS * ssaa) {
+            let escapes = sample_escapes(&Grid(band.to_vec()), generator, max_iter);
+            let colors = self.colors(&escapes, palette, max_iter, shift);
+            match colors.downsample_checked(ssaa, blending) {
+                Ok((Grid(band), _)) => rows.extend(band),
+                Err(e) => {
+                    eprintln!("couldn't render the export: {}", e);
+                    return;
+                }
+            }
+        }
+        let mut renderer = PngRenderer::new();
+        renderer.render(Grid(rows));
+        if let Err(e) = renderer.finish() {
+            eprintln!("couldn't render the export: {}", e);
+            return;
+        }
+        let path = (1..)
+            .map(|n| PathBuf::from(format!("export-{}.png", n)))
+            .find(|path| !Path::exists(path))
+            .unwrap();
+        match renderer.save(&path) {
+            Ok(()) => println!("saved {} ({}x{})", path.display(), width, height),
+            Err(e) => eprintln!("couldn't save {}: {}", path.display(), e),
+        }
+    }
+
+    fn coloring(&self) -> Coloring {
+        Coloring {
+            palette: self.palette,
+            heatmap: self.show_heatmap,
+            smooth: self.smooth,
+            cycling: self.cycling,
+        }
+    }
+
+    // how many colors cycling has moved the palette along by now
+    fn cycle_shift(&self) -> usize {
+        (self.cycle_start.elapsed().as_secs_f64() * self.cycle_speed) as usize
+    }
+
+    // colors how the orbits of a view escaped the way the keys have it set, whether the view
+    // was just sampled or is being recolored, with cycling colors moved `shift` along
+    fn colors<P: Palette<Item = Color>>(
+        &self,
+        escapes: &Grid<EscapeResult>,
+        palette: &P,
+        max_iter: usize,
+        shift: usize,
+    ) -> Grid<Color> {
+        let fractal = &self.fractals[self.fractal];
+        // cycling moves whole bands along the palette, so it shows them even when smooth colors
+        // are on
+        if self.show_heatmap {
+            heatmap_escapes(escapes, max_iter)
+        } else if self.cycling {
+            color_escapes(escapes, palette, max_iter, shift)
+        } else if self.smooth {
+            smooth_color_escapes(
+                escapes,
+                palette,
+                max_iter,
+                fractal.power,
+                fractal.generator.bailout(),
+                self.export_settings.blending,
+            )
+            .0
+        } else {
+            color_escapes(escapes, palette, max_iter, 0)
+        }
+    }
+
+    // draws everything that sits on top of the fractal without being part of it
+    fn draw_overlays(&self, image: &mut Image, dims: CanvasDims) {
+        if self.show_axes {
+            draw_axes(image, dims);
+        }
+        if let (true, Some(minimap)) = (self.show_minimap, &self.minimap) {
+            self.draw_minimap(image, minimap, dims);
+        }
+        if self.show_render_time {
+            let label = format!("{} ms", self.last_render.as_millis());
+            text::draw_label(image, OVERLAY_MARGIN, OVERLAY_MARGIN, &label);
+        }
+        if self.palette_switched.is_some() {
+            let label = self.palette_names[self.palette];
+            let y = image.height() - OVERLAY_MARGIN - text::label_size(label).1;
+            text::draw_label(image, OVERLAY_MARGIN, y, label);
         }
     }
 
@@ -68,6 +864,28 @@ impl CanvasState {
         }
 
         match (window_event, state.render_state.get_mut()) {
+            // recenter on the clicked point, keeping the zoom level
+            (
+                WindowEvent::MouseInput {
+                    state: ElementState::Pressed,
+                    button: MouseButton::Left,
+                    ..
+                },
+                RenderState::Done(min, max, position, _),
+            ) if state
+                .last_click
+                .is_some_and(|last| last.elapsed() < DOUBLE_CLICK_INTERVAL) =>
+            {
+                let dims = CanvasDims {
+                    min: *min,
+                    max: *max,
+                    rotation: state.rotation,
+                };
+                let dims = dims.recentered(dims.point_at(info, position.x, position.y));
+                state.last_click = None;
+                state.render_state = RefCell::new(RenderState::Recalc(dims.min, dims.max));
+                true
+            }
             // Enter zoom selection mode
             (
                 WindowEvent::MouseInput {
@@ -77,12 +895,14 @@ impl CanvasState {
                 },
                 RenderState::Done(min, max, position, image),
             ) => {
+                state.last_click = Some(Instant::now());
                 state.render_state = RefCell::new(RenderState::Dragging(DraggingState {
                     initial_click: *position,
                     current: *position,
                     dims: CanvasDims {
                         min: *min,
                         max: *max,
+                        rotation: state.rotation,
                     },
                     image: image.clone(),
                 }));
@@ -100,9 +920,49 @@ impl CanvasState {
                 pos.virtual_y = y;
                 pos.x = (x as f64 * info.dpi) as i32;
                 pos.y = ((info.height as i32 - y) as f64 * info.dpi) as i32;
+                let (x, y) = (pos.x, pos.y);
+                state.cursor = *pos;
+
+                if let RenderState::Dragging(dragging) = state.render_state.get_mut() {
+                    if state.fixed_aspect {
+                        let (x, y) = keep_aspect(dragging.initial_click, x, y, info);
+                        dragging.current.x = x;
+                        dragging.current.y = y;
+                    }
+                }
+
+                if let RenderState::Done(min, max, ..) = state.render_state.get_mut() {
+                    let dims = CanvasDims {
+                        min: *min,
+                        max: *max,
+                        rotation: state.rotation,
+                    };
+                    let preview = state.has_julia_preview() && state.julia_preview.visible;
+                    if preview {
+                        state.julia_preview.c = dims.point_at(info, x, y);
+                    }
+                    // the crosshair follows the cursor, but the fractal under it stays as it is
+                    if preview || state.show_crosshair {
+                        state.frame_stale = true;
+                        return true;
+                    }
+                }
                 // don't redraw on cursor movement
                 false
             }
+            (
+                WindowEvent::KeyboardInput {
+                    input:
+                        KeyboardInput {
+                            state: ElementState::Pressed,
+                            virtual_keycode: Some(key),
+                            modifiers,
+                            ..
+                        },
+                    ..
+                },
+                _,
+            ) => state.handle_key(*key, modifiers.shift),
             // setup state for recomputing a new scene
             (
                 WindowEvent::MouseInput {
@@ -112,6 +972,19 @@ impl CanvasState {
                 },
                 RenderState::Dragging(dragging_state),
             ) => {
+                // a click without a drag selects nothing, so keep the current view
+                if dragging_state.current.x == dragging_state.initial_click.x
+                    || dragging_state.current.y == dragging_state.initial_click.y
+                {
+                    state.render_state = RefCell::new(RenderState::Done(
+                        dragging_state.dims.min,
+                        dragging_state.dims.max,
+                        dragging_state.current,
+                        dragging_state.image.clone(),
+                    ));
+                    return false;
+                }
+
                 let (min_x, max_x) = if dragging_state.current.x < dragging_state.initial_click.x {
                     (dragging_state.current.x, dragging_state.initial_click.x)
                 } else {
@@ -124,20 +997,16 @@ impl CanvasState {
                     (dragging_state.initial_click.y, dragging_state.current.y)
                 };
 
-                let x_ratio =
-                    (dragging_state.dims.max.0 - dragging_state.dims.min.0) / info.width as f64;
-                let y_ratio =
-                    (dragging_state.dims.max.1 - dragging_state.dims.min.1) / info.height as f64;
-
-                let min_x = min_x as f64 * x_ratio + dragging_state.dims.min.0;
-                let max_x = max_x as f64 * x_ratio + dragging_state.dims.min.0;
-                let min_y = min_y as f64 * y_ratio + dragging_state.dims.min.1;
-                let max_y = max_y as f64 * y_ratio + dragging_state.dims.min.1;
+                let dims = dragging_state.dims;
+                let viewport = dims.viewport(info.width, info.height);
+                let min = viewport.pixel_to_point(f64::from(min_x), f64::from(min_y));
+                let max = viewport.pixel_to_point(f64::from(max_x), f64::from(max_y));
+                // the selection is upright on the screen, so it's the same shape in the turned
+                // view, only moved to where its middle is once that's turned
+                let middle = Bounds { min, max }.center();
+                let shift = dims.turned(middle) - middle;
 
-                state.render_state = RefCell::new(RenderState::Recalc(
-                    Point(min_x, min_y),
-                    Point(max_x, max_y),
-                ));
+                state.render_state = RefCell::new(RenderState::Recalc(min + shift, max + shift));
                 true
             }
             // reset to original view
@@ -149,6 +1018,7 @@ impl CanvasState {
                 },
                 render_state @ RenderState::Done(..),
             ) => {
+                state.rotation = state.initial_dims.rotation;
                 *render_state = RenderState::Recalc(state.initial_dims.min, state.initial_dims.max);
                 true
             }
@@ -184,27 +1054,213 @@ impl ManualClone for Image {
     }
 }
 
+// outlines the box between two corners, inclusive
+fn draw_box(
+    image: &mut Image,
+    (min_x, min_y): (usize, usize),
+    (max_x, max_y): (usize, usize),
+    color: Color,
+) {
+    for x in min_x..=max_x {
+        image[XY(x, min_y)] = color;
+        image[XY(x, max_y)] = color;
+    }
+    for y in min_y..=max_y {
+        image[XY(min_x, y)] = color;
+        image[XY(max_x, y)] = color;
+    }
+}
+
+fn draw_axes(image: &mut Image, dims: CanvasDims) {
+    let color = Color::WHITE;
+    let (width, height) = (image.width(), image.height());
+    let plot = |image: &mut Image, p: Point| {
+        let (x, y) = dims.pixel_of(p, image);
+        if x >= 0.0 && x < width as f64 && y >= 0.0 && y < height as f64 {
+            image[XY(x as usize, y as usize)] = color;
+        }
+    };
+
+    // whichever way the view is turned, the parts of the axes on it are no further from its
+    // center than its corners, so half-pixel steps along that much of them leave no gaps
+    let Point(center_x, center_y) = dims.center();
+    let (half_width, half_height) = dims.half_extents();
+    let reach = half_width.hypot(half_height);
+    let (pixel_width, pixel_height) = dims.viewport(width, height).pixel_size();
+    let step = pixel_width.min(pixel_height) / 2.0;
+    let steps = (2.0 * reach / step).min(1e6) as usize;
+    for i in 0..=steps {
+        let t = i as f64 * step - reach;
+        plot(image, Point(center_x + t, 0.0));
+        plot(image, Point(0.0, center_y + t));
+    }
+
+    // enough steps around the unit circle to leave no gaps in its outline
+    let steps = (4.0 * (1.0 / pixel_width + 1.0 / pixel_height)).min(1e6) as usize;
+    for step in 0..steps {
+        let angle = step as f64 / steps as f64 * std::f64::consts::TAU;
+        plot(image, Point(angle.cos(), angle.sin()));
+    }
+}
+
+// Moves the far corner of a selection from `start` to (x, y) so the selection has the same
+// shape as the canvas, growing it to cover the cursor but shrinking it to stay on the canvas
+fn keep_aspect(start: Position, x: i32, y: i32, info: &CanvasInfo) -> (i32, i32) {
+    let (width, height) = (info.width as f64, info.height as f64);
+    let (dx, dy) = (f64::from(x - start.x), f64::from(y - start.y));
+    // room from the start to the canvas edge in the direction being dragged
+    let room = |d: f64, from: i32, size: f64| {
+        if d < 0.0 {
+            f64::from(from)
+        } else {
+            size - 1.0 - f64::from(from)
+        }
+    };
+    let scale = (dx.abs() / width)
+        .max(dy.abs() / height)
+        .min(room(dx, start.x, width) / width)
+        .min(room(dy, start.y, height) / height)
+        .max(0.0);
+    let (sign_x, sign_y) = (dx.signum(), dy.signum());
+    (
+        start.x + (sign_x * scale * width).round() as i32,
+        start.y + (sign_y * scale * height).round() as i32,
+    )
+}
+
+// half the length of each crosshair line, in pixels
+const CROSSHAIR_SIZE: i32 = 8;
+
+// marks the cursor with a crosshair and labels it with the point underneath
+fn draw_crosshair(image: &mut Image, dims: CanvasDims, position: Position) {
+    let (width, height) = (image.width() as i32, image.height() as i32);
+    let (x, y) = (position.x, position.y);
+    if !(0..width).contains(&x) || !(0..height).contains(&y) {
+        return;
+    }
+    for d in -CROSSHAIR_SIZE..=CROSSHAIR_SIZE {
+        if (0..width).contains(&(x + d)) {
+            image[XY((x + d) as usize, y as usize)] = Color::WHITE;
+        }
+        if (0..height).contains(&(y + d)) {
+            image[XY(x as usize, (y + d) as usize)] = Color::WHITE;
+        }
+    }
+
+    let upright = dims
+        .viewport(image.width(), image.height())
+        .pixel_to_point(f64::from(x), f64::from(y));
+    let Point(re, im) = dims.turned(upright);
+    let label = format!("{:.6}{:+.6}i", re, im);
+    // keep the label beside the cursor, but on the image when the cursor is near an edge
+    let (label_width, label_height) = text::label_size(&label);
+    let offset = CROSSHAIR_SIZE as usize + 2;
+    let label_x = (x as usize + offset).min(image.width().saturating_sub(label_width));
+    let label_y = (y as usize + offset).min(image.height().saturating_sub(label_height));
+    text::draw_label(image, label_x, label_y, &label);
+}
+
+// If `next` is `previous` moved without zooming, returns how many pixels the image moved
+// by, along with `next` snapped to a whole number of pixels so the old ones line up.
+fn pan_offset(previous: CanvasDims, next: CanvasDims) -> Option<(isize, isize, CanvasDims)> {
+    // turned views move at an angle to the pixels
+    if previous.rotation != 0.0 || next.rotation != 0.0 {
+        return None;
+    }
+    let (half_w, half_h) = previous.half_extents();
+    let (next_half_w, next_half_h) = next.half_extents();
+    let same_zoom = |a: f64, b: f64| (a - b).abs() <= a.abs() * 1e-9;
+    if !same_zoom(half_w, next_half_w) || !same_zoom(half_h, next_half_h) {
+        return None;
+    }
+
+    let viewport = previous.viewport(RGB_WIDTH, RGB_HEIGHT);
+    let (dx, dy) = viewport.point_to_pixel(next.min);
+    let (dx, dy) = (dx.round(), dy.round());
+    if dx.abs() >= RGB_WIDTH as f64 || dy.abs() >= RGB_HEIGHT as f64 {
+        return None;
+    }
+
+    let shift = viewport.pixel_to_point(dx, dy) - previous.min;
+    let snapped = CanvasDims {
+        min: previous.min + shift,
+        max: previous.max + shift,
+        rotation: previous.rotation,
+    };
+    Some((dx as isize, dy as isize, snapped))
+}
+
+// Copies `grid` moved dx cells left and dy cells down, leaving the exposed cells as they were
+fn shift_grid<C: Copy>(grid: &Grid<C>, dx: isize, dy: isize) -> Grid<C> {
+    let height = grid.0.len() as isize;
+    let width = grid.0.first().map_or(0, Vec::len) as isize;
+    let mut shifted = grid.clone();
+    for y in 0..height {
+        for x in 0..width {
+            let (old_x, old_y) = (x + dx, y + dy);
+            if (0..width).contains(&old_x) && (0..height).contains(&old_y) {
+                shifted.0[y as usize][x as usize] = grid.0[old_y as usize][old_x as usize];
+            }
+        }
+    }
+    shifted
+}
+
+// The strips of the view that `shift_grid` leaves without old cells
+fn exposed_regions(dx: isize, dy: isize) -> Vec<Rect> {
+    let (w, h) = (dx.unsigned_abs(), dy.unsigned_abs());
+    let mut regions = vec![];
+    if w > 0 {
+        let x = if dx > 0 { RGB_WIDTH - w } else { 0 };
+        regions.push(Rect {
+            x,
+            y: 0,
+            w,
+            h: RGB_HEIGHT,
+        });
+    }
+    if h > 0 {
+        let y = if dy > 0 { RGB_HEIGHT - h } else { 0 };
+        regions.push(Rect {
+            x: 0,
+            y,
+            w: RGB_WIDTH,
+            h,
+        });
+    }
+    regions
+}
+
 pub const TERM_WIDTH: usize = 99;
 pub const TERM_HEIGHT: usize = 37;
 
 pub const RGB_WIDTH: usize = 960;
 pub const RGB_HEIGHT: usize = 640;
 
-pub fn zoomable_canvas_render<G: Generator, P: Palette<Item = Color>>(
-    generator: G,
-    palette: P,
+const PREVIEW_WIDTH: usize = 192;
+const PREVIEW_HEIGHT: usize = 128;
+const PREVIEW_MARGIN: usize = 8;
+const OVERLAY_MARGIN: usize = 8;
+const MINIMAP_WIDTH: usize = 192;
+const MINIMAP_HEIGHT: usize = 128;
+
+pub fn zoomable_canvas_render<P: Palette<Item = Color>>(
+    palettes: Vec<P>,
+    max_iter: usize,
 ) -> impl FnMut(&mut CanvasState, &mut Image)
 where
-    G: Sync,
     P: Sync,
 {
     move |canvas_state, image| {
+        let palette = &palettes[canvas_state.palette];
+        // the finished view recolored without cycling, which it's kept as from then on
+        let mut recolored = None;
         match &*canvas_state.render_state.borrow() {
             RenderState::Dragging(DraggingState {
                 initial_click,
                 current,
+                dims,
                 image: previous_image,
-                ..
             }) => {
                 previous_image.clone_onto(image);
                 let highlight_color = Color::rgb(0, 255, 0);
@@ -228,25 +1284,159 @@ where
                     image[XY(start_x, y)] = highlight_color;
                     image[XY(end_x, y)] = highlight_color;
                 }
+                canvas_state.draw_overlays(image, *dims);
             }
             RenderState::Recalc(min, max) => {
-                let grid = Grid::new(RGB_WIDTH, RGB_HEIGHT, *min, *max);
-                let mut renderer = RGBRenderer::new(image);
-                draw(&generator, &palette, &mut renderer, &grid);
+                let mut dims = CanvasDims {
+                    min: *min,
+                    max: *max,
+                    rotation: canvas_state.rotation,
+                };
+                let generator = &canvas_state.fractals[canvas_state.fractal].generator;
+                let start = Instant::now();
+                let fractal = canvas_state.fractal;
+                let cached = canvas_state
+                    .cache
+                    .get(RenderKey::new(fractal, dims, image, max_iter))
+                    .cloned();
+                let escapes = match cached {
+                    Some(cached) => cached,
+                    None => {
+                        let pan = canvas_state.escapes.as_ref().and_then(
+                            |(previous, previous_escapes)| {
+                                let (dx, dy, snapped) = pan_offset(*previous, dims)?;
+                                Some((dx, dy, snapped, previous_escapes))
+                            },
+                        );
+                        let escapes = if let Some((dx, dy, snapped, previous_escapes)) = pan {
+                            dims = snapped;
+                            let mut escapes = shift_grid(previous_escapes, dx, dy);
+                            let grid = dims.grid(RGB_WIDTH, RGB_HEIGHT);
+                            for region in exposed_regions(dx, dy) {
+                                let sampled =
+                                    sample_escapes(&grid.region(region), generator, max_iter);
+                                for (row, sampled) in
+                                    escapes.0[region.y..].iter_mut().zip(sampled.0)
+                                {
+                                    row[region.x..region.x + region.w].copy_from_slice(&sampled);
+                                }
+                            }
+                            escapes
+                        } else {
+                            sample_escapes(&dims.grid(RGB_WIDTH, RGB_HEIGHT), generator, max_iter)
+                        };
+                        let key = RenderKey::new(fractal, dims, image, max_iter);
+                        canvas_state.cache.insert(key, &escapes);
+                        escapes
+                    }
+                };
+                let shift = canvas_state.cycle_shift();
+                let colors = canvas_state.colors(&escapes, palette, max_iter, shift);
+                RGBRenderer::new(image).render(colors);
+                canvas_state.last_render = start.elapsed();
+                canvas_state.escapes = Some((dims, escapes));
+                canvas_state.drawn = canvas_state.coloring();
+            }
+            RenderState::Done(min, max, position, done_image) => {
+                if canvas_state
+                    .palette_switched
+                    .is_some_and(|switched| switched.elapsed() >= PALETTE_LABEL_TIME)
+                {
+                    canvas_state.palette_switched = None;
+                    canvas_state.frame_stale = true;
+                }
+                if canvas_state.frame_stale || canvas_state.cycling {
+                    let dims = CanvasDims {
+                        min: *min,
+                        max: *max,
+                        rotation: canvas_state.rotation,
+                    };
+                    // switching how the view is colored recolors it from how its orbits escaped,
+                    // so that it's never sampled again
+                    if canvas_state.cycling || canvas_state.coloring() != canvas_state.drawn {
+                        let (_, escapes) = canvas_state.escapes.as_ref().unwrap();
+                        let shift = canvas_state.cycle_shift();
+                        let colors = canvas_state.colors(escapes, palette, max_iter, shift);
+                        RGBRenderer::new(image).render(colors);
+                        if !canvas_state.cycling {
+                            recolored = Some(image.clone());
+                        }
+                    } else {
+                        done_image.clone_onto(image);
+                    }
+                    let preview = &canvas_state.julia_preview;
+                    if canvas_state.has_julia_preview() && preview.visible {
+                        let grid = Grid::new(
+                            PREVIEW_WIDTH,
+                            PREVIEW_HEIGHT,
+                            Point(-1.5, -1.5),
+                            Point(1.5, 1.5),
+                        );
+                        let x = image.width() - PREVIEW_WIDTH - PREVIEW_MARGIN;
+                        let y = image.height() - PREVIEW_HEIGHT - PREVIEW_MARGIN;
+                        let mut renderer = RGBRenderer::at(image, x, y);
+                        draw(
+                            &Julia::new(preview.c),
+                            palette,
+                            max_iter,
+                            &mut renderer,
+                            &grid,
+                        )
+                        .unwrap();
+                    }
+                    if canvas_state.show_minimap && canvas_state.minimap.is_none() {
+                        let mut minimap = Image::new(MINIMAP_WIDTH, MINIMAP_HEIGHT);
+                        let initial = canvas_state.initial_dims;
+                        let grid = initial.grid(MINIMAP_WIDTH, MINIMAP_HEIGHT);
+                        let generator = &canvas_state.fractals[canvas_state.fractal].generator;
+                        draw(
+                            generator,
+                            palette,
+                            max_iter,
+                            &mut RGBRenderer::new(&mut minimap),
+                            &grid,
+                        )
+                        .unwrap();
+                        canvas_state.minimap = Some(minimap);
+                    }
+                    canvas_state.draw_overlays(image, dims);
+                    if canvas_state.show_crosshair {
+                        draw_crosshair(image, dims, *position);
+                    }
+                    canvas_state.frame_stale = false;
+                    match canvas_state.export {
+                        Export::Idle => {}
+                        Export::Requested => {
+                            let label = "rendering export...";
+                            let y = image.height() - OVERLAY_MARGIN - text::label_size(label).1;
+                            text::draw_label(image, OVERLAY_MARGIN, y, label);
+                            canvas_state.export = Export::Labelled;
+                            canvas_state.frame_stale = true;
+                        }
+                        Export::Labelled => {
+                            canvas_state.export(palette, max_iter, *min, *max);
+                            canvas_state.export = Export::Idle;
+                        }
+                    }
+                }
             }
-            _ => {}
         }
 
-        let min;
-        let max;
-        if let RenderState::Recalc(rcmin, rcmax) = &*canvas_state.render_state.borrow() {
-            min = *rcmin;
-            max = *rcmax;
-        } else {
-            return;
+        if let Some(recolored) = recolored {
+            if let RenderState::Done(.., done_image) = &mut *canvas_state.render_state.borrow_mut()
+            {
+                *done_image = recolored;
+            }
+            canvas_state.drawn = canvas_state.coloring();
         }
 
+        let dims = match (&*canvas_state.render_state.borrow(), &canvas_state.escapes) {
+            (RenderState::Recalc(..), Some((dims, _))) => *dims,
+            _ => return,
+        };
+
         *canvas_state.render_state.borrow_mut() =
-            RenderState::Done(min, max, Position::new(), image.clone());
+            RenderState::Done(dims.min, dims.max, canvas_state.cursor, image.clone());
+        canvas_state.frame_stale = true;
     }
 }
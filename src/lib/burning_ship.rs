@@ -0,0 +1,23 @@
+use crate::fractal::{Generator, Point};
+
+/// The Burning Ship fractal, which takes the absolute value of both parts of `z` before squaring.
+#[derive(Default)]
+pub struct BurningShip;
+
+impl BurningShip {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Generator for BurningShip {
+    type Output = impl Iterator<Item = Point>;
+
+    fn generate(&self, p: Point) -> Self::Output {
+        (0u32..).scan(Point(0.0, 0.0), move |acc, _| {
+            let Point(x, y) = *acc;
+            *acc = Point::next(p, Point(x.abs(), y.abs()));
+            Some(*acc)
+        })
+    }
+}
@@ -0,0 +1,33 @@
+use crate::fractal::{Generator, Point};
+
+/// The "Celtic" Mandelbrot, which takes the absolute value of the real part of `z^2`.
+#[derive(Default)]
+pub struct Celtic;
+
+impl Celtic {
+    pub fn new() -> Self {
+        Self
+    }
+
+    fn next(Point(u, v): Point, Point(x, y): Point) -> Point {
+        Point((x * x - y * y).abs() + u, 2.0 * x * y + v)
+    }
+}
+
+impl Generator for Celtic {
+    type Output = impl Iterator<Item = Point>;
+
+    fn generate(&self, p: Point) -> Self::Output {
+        (0u32..).scan(Point(0.0, 0.0), move |acc, _| {
+            *acc = Celtic::next(p, *acc);
+            Some(*acc)
+        })
+    }
+}
+
+#[test]
+fn celtic_test() {
+    // z^2 has a negative real part at i, which the Celtic step folds back
+    let Point(x, y) = Celtic::next(Point(0.0, 0.0), Point(0.0, 1.0));
+    assert!((x - 1.0).abs() < 1e-9 && y.abs() < 1e-9);
+}
@@ -1,8 +1,15 @@
 use crate::fractal::{Grid, Palette, Renderer};
+use std::io::{self, Write};
 
 pub struct CharPalette;
 
 const CHAR_PALETTE: &str = "  ,.'\"~:;o-!|?/<>X+={^0#%&@8*$";
+const CHAR_INTERIOR: char = '$';
+
+/// Characters for values that diverge around zero, from most negative to most positive.
+pub const CHAR_DIVERGING: &[char] = &[
+    '$', '#', '0', '=', '|', ';', ' ', '.', ',', '\'', '"', '~', ':',
+];
 
 impl Palette for CharPalette {
     type Item = char;
@@ -11,6 +18,10 @@ impl Palette for CharPalette {
     fn get(&self) -> Self::Output {
         CHAR_PALETTE.chars()
     }
+
+    fn interior(&self) -> char {
+        CHAR_INTERIOR
+    }
 }
 
 pub struct CharRenderer;
@@ -23,4 +34,8 @@ impl Renderer for CharRenderer {
             println!("{}", row.iter().collect::<String>())
         }
     }
+
+    fn finish(&mut self) -> io::Result<()> {
+        io::stdout().flush()
+    }
 }
@@ -0,0 +1,44 @@
+use crate::fractal::{Generator, Point};
+use std::f64::consts::PI;
+
+/// The Collatz map `n -> n / 2` or `n -> 3n + 1`, extended to the complex plane.
+#[derive(Default)]
+pub struct Collatz;
+
+impl Collatz {
+    pub fn new() -> Self {
+        Self
+    }
+
+    // (2 + 7z - (2 + 5z)cos(πz)) / 4
+    fn step(z: Point) -> Point {
+        let two = Point(2.0, 0.0);
+        (two + z * 7.0 - (two + z * 5.0) * (z * PI).cos()) * 0.25
+    }
+}
+
+impl Generator for Collatz {
+    type Output = impl Iterator<Item = Point>;
+
+    fn generate(&self, p: Point) -> Self::Output {
+        (0u32..).scan(p, |acc, _| {
+            *acc = Collatz::step(*acc);
+            Some(*acc)
+        })
+    }
+}
+
+#[test]
+fn collatz_integers_test() {
+    // on the integers the map matches the classic definition: 3 -> 10 -> 5 -> 16 -> 8
+    let orbit = Collatz::new().generate(Point(3.0, 0.0));
+    for (expected, Point(x, y)) in [10.0, 5.0, 16.0, 8.0].iter().zip(orbit) {
+        assert!(
+            (expected - x).abs() < 1e-9,
+            "Expected {} to match {}",
+            expected,
+            x
+        );
+        assert!(y.abs() < 1e-9);
+    }
+}
@@ -0,0 +1,171 @@
+use crate::fractal::{Grid, Palette};
+
+/// How values are spread over a palette.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Scale {
+    Linear,
+    /// Compresses large values, which keeps detail in fields where a few cells dwarf the rest.
+    Logarithmic,
+}
+
+impl Scale {
+    // maps `value` in `0..=range` onto `0.0..=1.0`
+    fn normalize(self, value: u32, range: u32) -> f64 {
+        if range == 0 {
+            return 0.0;
+        }
+        match self {
+            Scale::Linear => f64::from(value) / f64::from(range),
+            Scale::Logarithmic => f64::from(value).ln_1p() / f64::from(range).ln_1p(),
+        }
+    }
+}
+
+/// Colors a scalar field such as iteration counts or hit densities, stretching its values
+/// so the smallest one gets the first palette item and the largest one the last.
+pub fn colorize<P: Palette>(grid: &Grid<u32>, palette: &P, scale: Scale) -> Grid<P::Item> {
+    let colors: Vec<_> = palette.get().collect();
+    assert!(!colors.is_empty(), "can't colorize with an empty palette");
+
+    let values = || grid.0.iter().flatten().cloned();
+    let min = values().min().unwrap_or(0);
+    let max = values().max().unwrap_or(0);
+    let last = (colors.len() - 1) as f64;
+
+    Grid(
+        grid.0
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&value| {
+                        let t = scale.normalize(value - min, max - min);
+                        colors[(t * last).round() as usize]
+                    })
+                    .collect()
+            })
+            .collect(),
+    )
+}
+
+/// A palette spread over a fixed number of iterations, so escape counts can pick colors on
+/// a logarithmic scale instead of stepping through the palette one color per iteration.
+#[derive(Clone)]
+pub struct Stretched<C> {
+    colors: Vec<C>,
+    interior: C,
+}
+
+impl<C: Copy> Stretched<C> {
+    /// `exponent` bends the scale before colors are picked: above 1 it spends more of the
+    /// palette on late iterations, below 1 on early ones.
+    pub fn new<P: Palette<Item = C>>(
+        palette: &P,
+        scale: Scale,
+        iterations: u32,
+        exponent: f64,
+    ) -> Self {
+        let colors: Vec<_> = palette.get().collect();
+        assert!(!colors.is_empty(), "can't stretch an empty palette");
+        let last = (colors.len() - 1) as f64;
+        Stretched {
+            colors: (0..iterations)
+                .map(|i| {
+                    let t = scale
+                        .normalize(i, iterations.saturating_sub(1))
+                        .powf(exponent);
+                    colors[(t * last).round() as usize]
+                })
+                .collect(),
+            interior: palette.interior(),
+        }
+    }
+}
+
+impl<C: Copy> Palette for Stretched<C> {
+    type Item = C;
+    type Output = std::vec::IntoIter<C>;
+
+    fn get(&self) -> Self::Output {
+        self.colors.clone().into_iter()
+    }
+
+    fn interior(&self) -> C {
+        self.interior
+    }
+}
+
+/// Lays a palette out left to right as a strip, with each color `width` cells wide and
+/// `height` cells tall.
+pub fn swatch<P: Palette>(palette: &P, width: usize, height: usize) -> Grid<P::Item> {
+    let row: Vec<_> = palette
+        .get()
+        .flat_map(|c| std::iter::repeat_n(c, width))
+        .collect();
+    Grid(vec![row; height])
+}
+
+#[cfg(test)]
+struct TestPalette(Vec<u32>);
+
+#[cfg(test)]
+impl Palette for TestPalette {
+    type Item = u32;
+    type Output = std::vec::IntoIter<u32>;
+
+    fn get(&self) -> Self::Output {
+        self.0.clone().into_iter()
+    }
+
+    fn interior(&self) -> u32 {
+        0
+    }
+}
+
+#[test]
+fn colorize_linear_test() {
+    let palette = TestPalette(vec![0, 1, 2, 3, 4]);
+    let grid = Grid(vec![vec![10, 11, 12], vec![13, 14, 14]]);
+    assert_eq!(
+        colorize(&grid, &palette, Scale::Linear).0,
+        vec![vec![0, 1, 2], vec![3, 4, 4]]
+    );
+}
+
+#[test]
+fn colorize_logarithmic_test() {
+    let palette = TestPalette((0..=100).collect());
+    let grid = Grid(vec![vec![0, 9, 99, 999]]);
+    // each step is ten times bigger, so it covers a third of the palette
+    assert_eq!(
+        colorize(&grid, &palette, Scale::Logarithmic).0,
+        vec![vec![0, 33, 67, 100]]
+    );
+}
+
+#[test]
+fn colorize_flat_test() {
+    let palette = TestPalette(vec![7, 8]);
+    let grid = Grid(vec![vec![5, 5], vec![5, 5]]);
+    assert_eq!(
+        colorize(&grid, &palette, Scale::Logarithmic).0,
+        vec![vec![7, 7], vec![7, 7]]
+    );
+}
+
+#[test]
+fn stretched_test() {
+    let palette = TestPalette(vec![0, 1, 2]);
+    let linear = Stretched::new(&palette, Scale::Linear, 3, 1.0);
+    assert_eq!(linear.get().collect::<Vec<_>>(), vec![0, 1, 2]);
+    // later iterations share colors, leaving more of the palette for the first few
+    let log = Stretched::new(&palette, Scale::Logarithmic, 8, 1.0);
+    assert_eq!(log.get().collect::<Vec<_>>(), vec![0, 1, 1, 1, 2, 2, 2, 2]);
+    let squared = Stretched::new(&palette, Scale::Linear, 5, 2.0);
+    assert_eq!(squared.get().collect::<Vec<_>>(), vec![0, 0, 1, 1, 2]);
+}
+
+#[test]
+fn swatch_test() {
+    let palette = TestPalette(vec![1, 2]);
+    assert_eq!(swatch(&palette, 2, 2).0, vec![vec![1, 1, 2, 2]; 2]);
+}
@@ -0,0 +1,169 @@
+use crate::{
+    fractal::{Generator, Point},
+    precision::Real,
+};
+use std::sync::Arc;
+
+/// The Mandelbrot set around a center given in any precision `T`. Points handed to `generate`
+/// are offsets from that center, which an `f64` can hold to full precision however small they
+/// get, so with a precise enough `T` a view can zoom in far past where an `f64` center would
+/// leave neighbouring pixels with the same value.
+pub struct DeepMandelbrot<T> {
+    center: (T, T),
+}
+
+impl<T: Real> DeepMandelbrot<T> {
+    pub fn new(re: T, im: T) -> Self {
+        Self { center: (re, im) }
+    }
+
+    fn next((u, v): (T, T), (x, y): (T, T)) -> (T, T) {
+        (x * x - y * y + u, (x + x) * y + v)
+    }
+}
+
+impl<T: Real + Send + Sync + 'static> Generator for DeepMandelbrot<T> {
+    type Output = impl Iterator<Item = Point>;
+
+    fn generate(&self, Point(dx, dy): Point) -> Self::Output {
+        let (re, im) = self.center;
+        let c = (re + T::from_f64(dx), im + T::from_f64(dy));
+        (0u32..).scan((T::from_f64(0.0), T::from_f64(0.0)), move |z, _| {
+            *z = DeepMandelbrot::next(c, *z);
+            Some(Point(z.0.to_f64(), z.1.to_f64()))
+        })
+    }
+}
+
+// an orbit that gets this much closer to zero than the reference, relative to the square of
+// its size, has lost too many digits to its delta to be trusted
+const GLITCH_TOLERANCE: f64 = 1e-6;
+
+/// The Mandelbrot set around a center in precision `T`, like `DeepMandelbrot`, but computing
+/// only one orbit in that precision, for the center itself. Every other point follows that
+/// reference orbit in `f64` by tracking how far it has drifted from it, which is nearly as fast
+/// as iterating in `f64` outright.
+///
+/// Where the drift stops being accurate, because the orbit passes much closer to zero than the
+/// reference does, or the reference has already escaped, that point starts over in `T`.
+pub struct PerturbedMandelbrot<T> {
+    center: (T, T),
+    reference: Arc<Vec<Point>>,
+}
+
+impl<T: Real> PerturbedMandelbrot<T> {
+    /// Computes the reference orbit, for up to `max_iter` steps or until it escapes.
+    pub fn new(re: T, im: T, max_iter: usize) -> Self {
+        let center = (re, im);
+        let mut z = (T::from_f64(0.0), T::from_f64(0.0));
+        let mut reference = vec![Point(0.0, 0.0)];
+        while reference.len() <= max_iter && reference.last().unwrap().magnitude_squared() < 100.0 {
+            z = DeepMandelbrot::next(center, z);
+            reference.push(Point(z.0.to_f64(), z.1.to_f64()));
+        }
+        Self {
+            center,
+            reference: Arc::new(reference),
+        }
+    }
+}
+
+enum Orbit<T> {
+    // steps taken and drift from the reference after them
+    Perturbed(usize, Point),
+    Direct((T, T)),
+}
+
+impl<T: Real + Send + Sync + 'static> Generator for PerturbedMandelbrot<T> {
+    type Output = impl Iterator<Item = Point>;
+
+    fn generate(&self, offset: Point) -> Self::Output {
+        let (re, im) = self.center;
+        let c = (re + T::from_f64(offset.0), im + T::from_f64(offset.1));
+        let reference = Arc::clone(&self.reference);
+        let to_point = |(x, y): (T, T)| Point(x.to_f64(), y.to_f64());
+        (0u32..).scan(Orbit::Perturbed(0, Point(0.0, 0.0)), move |orbit, _| {
+            match orbit {
+                Orbit::Perturbed(steps, drift) => {
+                    // z + d squared plus c + offset, less z squared plus c
+                    let next_drift = reference[*steps] * *drift * 2.0 + *drift * *drift + offset;
+                    *steps += 1;
+                    if let Some(&z) = reference.get(*steps) {
+                        let next = z + next_drift;
+                        if next.magnitude_squared() >= GLITCH_TOLERANCE * z.magnitude_squared() {
+                            *drift = next_drift;
+                            return Some(next);
+                        }
+                    }
+                    let mut z = (T::from_f64(0.0), T::from_f64(0.0));
+                    for _ in 0..*steps {
+                        z = DeepMandelbrot::next(c, z);
+                    }
+                    *orbit = Orbit::Direct(z);
+                    Some(to_point(z))
+                }
+                Orbit::Direct(z) => {
+                    *z = DeepMandelbrot::next(c, *z);
+                    Some(to_point(*z))
+                }
+            }
+        })
+    }
+}
+
+#[test]
+fn deep_mandelbrot_test() {
+    use crate::{mandelbrot::Mandelbrot, precision::DoubleDouble};
+    // in f64 this is the plain Mandelbrot set moved over
+    let c = Point(-0.4, 0.6);
+    let deep = DeepMandelbrot::new(-0.4, 0.6).generate(Point(0.0, 0.0));
+    let mandelbrot = Mandelbrot::new(Point(0.0, 0.0)).generate(c);
+    for (Point(x, y), Point(u, v)) in deep.zip(mandelbrot).take(20) {
+        assert_eq!((x, y), (u, v));
+    }
+
+    // z -> z^2 - 1.9 is chaotic on the real line, so a nudge of 1e-20 grows until it shows,
+    // but only if the center and the nudge can be added without losing it
+    let step = |orbit: &mut dyn Iterator<Item = Point>| orbit.nth(80).unwrap().0;
+    let f64_center = DeepMandelbrot::new(-1.9, 0.0);
+    assert_eq!(
+        step(&mut f64_center.generate(Point(0.0, 0.0))),
+        step(&mut f64_center.generate(Point(1e-20, 0.0)))
+    );
+    let precise_center = DeepMandelbrot::new(DoubleDouble::from_f64(-1.9), Real::from_f64(0.0));
+    let (a, b) = (
+        step(&mut precise_center.generate(Point(0.0, 0.0))),
+        step(&mut precise_center.generate(Point(1e-20, 0.0))),
+    );
+    assert!((a - b).abs() > 1e-3, "{} {}", a, b);
+}
+
+#[test]
+fn perturbed_mandelbrot_test() {
+    use crate::mandelbrot::Mandelbrot;
+    let mandelbrot = Mandelbrot::new(Point(0.0, 0.0));
+    let perturbed = PerturbedMandelbrot::new(-0.75, 0.1, 200);
+    let escape = |orbit: &mut dyn Iterator<Item = Point>| {
+        orbit.take(200).position(|z| z.magnitude_squared() >= 100.0)
+    };
+    // rounding differs from iterating directly, which can tip a few points on the boundary
+    let offsets: Vec<_> = (0..400)
+        .map(|i| Point((i % 20) as f64 * 1e-3 - 0.01, (i / 20) as f64 * 1e-3 - 0.01))
+        .collect();
+    let matching = offsets
+        .iter()
+        .filter(|&&d| {
+            escape(&mut perturbed.generate(d))
+                == escape(&mut mandelbrot.generate(Point(-0.75, 0.1) + d))
+        })
+        .count();
+    assert!(matching >= 390, "{}", matching);
+
+    // the reference escapes right away, so points inside have to start over directly
+    let outside = PerturbedMandelbrot::new(0.5, 0.0, 200);
+    assert_eq!(escape(&mut outside.generate(Point(-0.6, 0.0))), None);
+    assert_eq!(
+        escape(&mut outside.generate(Point(0.0, 0.0))),
+        escape(&mut mandelbrot.generate(Point(0.5, 0.0)))
+    );
+}
@@ -0,0 +1,52 @@
+use crate::fractal::{Generator, Point};
+
+// Once the real part passes this, the next step's magnitude is around e^50 and the orbit
+// won't come back. The usual radius of 10 would be far too early: exp folds huge points with
+// negative real parts straight back to zero.
+const BAILOUT: f64 = 50.0;
+
+/// The exponential map, iterating `z -> c exp(z)` from zero.
+#[derive(Default)]
+pub struct ExpFractal;
+
+impl ExpFractal {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Generator for ExpFractal {
+    type Output = impl Iterator<Item = Point>;
+
+    fn generate(&self, p: Point) -> Self::Output {
+        (0u32..).scan(Point(0.0, 0.0), move |acc, _| {
+            *acc = p * acc.exp();
+            Some(*acc)
+        })
+    }
+
+    fn escaped(&self, Point(x, _): Point) -> bool {
+        x > BAILOUT
+    }
+
+    // an escaped orbit is at least this far out, as its real part alone is
+    fn bailout(&self) -> f64 {
+        BAILOUT
+    }
+}
+
+#[test]
+fn exp_fractal_test() {
+    let fractal = ExpFractal::new();
+    // small multipliers settle on a fixed point, larger ones run off to the right
+    assert!(!fractal
+        .generate(Point(0.2, 0.0))
+        .take(100)
+        .any(|z| fractal.escaped(z)));
+    assert!(fractal
+        .generate(Point(1.0, 0.0))
+        .take(100)
+        .any(|z| fractal.escaped(z)));
+    let r = fractal.bailout();
+    assert!(fractal.escaped(Point(r * 1.01, 0.0)) && !fractal.escaped(Point(r * 0.99, 0.0)));
+}
@@ -0,0 +1,197 @@
+use crate::fractal::{Grid, Renderer};
+use pixel_canvas::Color;
+use std::{fs::File, io, io::BufWriter, io::Write, path::Path};
+
+/// Collects a rendered grid so it can be encoded as a PNG, or with the `image` feature, as a
+/// JPEG or BMP.
+pub struct PngRenderer {
+    grid: Option<Grid<Color>>,
+    quality: u8,
+}
+
+impl PngRenderer {
+    pub fn new() -> Self {
+        Self {
+            grid: None,
+            quality: DEFAULT_QUALITY,
+        }
+    }
+
+    /// Sets the JPEG quality, from 1 to 100.
+    pub fn with_quality(self, quality: u8) -> Self {
+        Self { quality, ..self }
+    }
+
+    /// The grid last rendered, if anything has been.
+    pub fn into_grid(self) -> Option<Grid<Color>> {
+        self.grid
+    }
+
+    /// Saves the render in the format named by the file's extension: `.png`, `.ppm`, `.jpg`,
+    /// `.jpeg` or `.bmp`. Other extensions are refused rather than writing a misnamed file.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("png") => self.write(BufWriter::new(File::create(path)?)),
+            Some("ppm") => self.write_ppm(BufWriter::new(File::create(path)?)),
+            Some("jpg") | Some("jpeg") => self.save_with(path, Encoding::Jpeg),
+            Some("bmp") => self.save_with(path, Encoding::Bmp),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "can't tell what format to save {} in, use .png, .ppm, .jpg or .bmp",
+                    path.display()
+                ),
+            )),
+        }
+    }
+
+    #[cfg(feature = "image")]
+    fn save_with(&self, path: &Path, encoding: Encoding) -> io::Result<()> {
+        let grid = self
+            .grid
+            .as_ref()
+            .ok_or_else(|| io::Error::other("nothing was rendered"))?;
+        let image = to_rgb_image(grid);
+        let mut file = BufWriter::new(File::create(path)?);
+        let result = match encoding {
+            Encoding::Jpeg => {
+                image::codecs::jpeg::JpegEncoder::new_with_quality(&mut file, self.quality)
+                    .encode_image(&image)
+            }
+            Encoding::Bmp => image::codecs::bmp::BmpEncoder::new(&mut file).encode(
+                &image,
+                image.width(),
+                image.height(),
+                image::ColorType::Rgb8,
+            ),
+        };
+        result.map_err(io::Error::other)
+    }
+
+    #[cfg(not(feature = "image"))]
+    fn save_with(&self, path: &Path, encoding: Encoding) -> io::Result<()> {
+        Err(io::Error::other(format!(
+            "saving {} as {:?} needs the image feature",
+            path.display(),
+            encoding
+        )))
+    }
+
+    pub fn write<W: Write>(&self, w: W) -> io::Result<()> {
+        let (width, height, data) = self.top_down_rgb()?;
+
+        let mut encoder = png::Encoder::new(w, width as u32, height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+
+        writer.write_image_data(&data)?;
+        Ok(())
+    }
+
+    /// Writes the render as a binary PPM, an uncompressed format simple enough to compare
+    /// byte for byte.
+    pub fn write_ppm<W: Write>(&self, mut w: W) -> io::Result<()> {
+        let (width, height, data) = self.top_down_rgb()?;
+        write!(w, "P6\n{} {}\n255\n", width, height)?;
+        w.write_all(&data)?;
+        w.flush()
+    }
+
+    // the width, height and RGB bytes of the render, starting from the top row
+    fn top_down_rgb(&self) -> io::Result<(usize, usize, Vec<u8>)> {
+        let rows = match &self.grid {
+            Some(Grid(rows)) => rows,
+            None => return Err(io::Error::other("nothing was rendered")),
+        };
+        let height = rows.len();
+        let width = rows.first().map_or(0, Vec::len);
+        // the first grid row is the bottom of the view, but images start at the top
+        let data: Vec<u8> = rows
+            .iter()
+            .rev()
+            .flatten()
+            .flat_map(|color| [color.r, color.g, color.b])
+            .collect();
+        Ok((width, height, data))
+    }
+}
+
+const DEFAULT_QUALITY: u8 = 90;
+
+impl Default for PngRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// the formats that need the image crate to encode
+#[derive(Debug)]
+enum Encoding {
+    Jpeg,
+    Bmp,
+}
+
+impl Renderer for PngRenderer {
+    type Item = Color;
+
+    fn render(&mut self, grid: Grid<Color>) {
+        self.grid = Some(grid);
+    }
+}
+
+/// Converts a rendered grid into an `image::RgbImage`, flipped like the PNG export so the top
+/// of the view is the top of the image.
+#[cfg(feature = "image")]
+pub fn to_rgb_image(Grid(rows): &Grid<Color>) -> image::RgbImage {
+    let height = rows.len();
+    let width = rows.first().map_or(0, Vec::len);
+    image::RgbImage::from_fn(width as u32, height as u32, |x, y| {
+        let color = rows[height - 1 - y as usize][x as usize];
+        image::Rgb([color.r, color.g, color.b])
+    })
+}
+
+/// Collects a rendered grid as an `image::RgbImage`, for saving in other formats or further
+/// processing with the `image` crate.
+#[cfg(feature = "image")]
+#[derive(Default)]
+pub struct RgbImageRenderer {
+    image: Option<image::RgbImage>,
+}
+
+#[cfg(feature = "image")]
+impl RgbImageRenderer {
+    pub fn new() -> Self {
+        Self { image: None }
+    }
+
+    /// The last image rendered, if there was one.
+    pub fn into_image(self) -> Option<image::RgbImage> {
+        self.image
+    }
+}
+
+#[cfg(feature = "image")]
+impl Renderer for RgbImageRenderer {
+    type Item = Color;
+
+    fn render(&mut self, grid: Grid<Color>) {
+        self.image = Some(to_rgb_image(&grid));
+    }
+}
+
+#[cfg(feature = "image")]
+#[test]
+fn to_rgb_image_test() {
+    let (bottom, top) = (Color::rgb(1, 2, 3), Color::rgb(4, 5, 6));
+    let image = to_rgb_image(&Grid(vec![vec![bottom; 2], vec![top; 2]]));
+    assert_eq!(image.dimensions(), (2, 2));
+    assert_eq!(image.get_pixel(1, 0), &image::Rgb([4, 5, 6]));
+    assert_eq!(image.get_pixel(0, 1), &image::Rgb([1, 2, 3]));
+}
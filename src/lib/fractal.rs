@@ -1,70 +1,696 @@
+use crate::view::Viewport;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::Cell,
+    fmt, io,
+    ops::{Add, Div, Mul, Sub},
+    str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
 pub struct Point(pub f64, pub f64);
 
+/// How far from the origin orbits go before `Generator::escaped` counts them as escaping,
+/// unless the generator says otherwise.
+pub const ESCAPE_RADIUS: f64 = 10.0;
+
 impl Point {
     pub fn next(Point(u, v): Point, Point(x, y): Point) -> Point {
         Point(x * x - y * y + u, 2.0 * x * y + v)
     }
 
-    fn fairly_close(Point(u, v): Point) -> bool {
-        (u * u + v * v) < 100.0
+    fn fairly_close(p: Point) -> bool {
+        p.magnitude_squared() < ESCAPE_RADIUS * ESCAPE_RADIUS
+    }
+
+    /// The squared distance from the origin, `x² + y²`, which is cheaper than `magnitude` and
+    /// enough for comparing distances.
+    pub fn magnitude_squared(&self) -> f64 {
+        self.0 * self.0 + self.1 * self.1
+    }
+
+    /// The distance from the origin, `|x + iy|`, without overflowing for large parts.
+    pub fn magnitude(&self) -> f64 {
+        self.0.hypot(self.1)
+    }
+
+    /// The point turned `angle` radians anticlockwise about `center`. Turning by no angle at
+    /// all leaves it exactly where it was.
+    pub fn rotated_about(self, center: Point, angle: f64) -> Point {
+        if angle == 0.0 {
+            return self;
+        }
+        let (sin, cos) = angle.sin_cos();
+        let Point(x, y) = self - center;
+        center + Point(x * cos - y * sin, x * sin + y * cos)
+    }
+
+    pub(crate) fn is_finite(self) -> bool {
+        self.0.is_finite() && self.1.is_finite()
+    }
+
+    /// Complex conjugate, `x - iy`.
+    pub fn conj(self) -> Point {
+        Point(self.0, -self.1)
+    }
+
+    /// Complex cosine, `cos(x + iy) = cos x cosh y - i sin x sinh y`.
+    pub fn cos(self) -> Point {
+        let Point(x, y) = self;
+        Point(x.cos() * y.cosh(), -x.sin() * y.sinh())
+    }
+
+    /// Complex sine, `sin(x + iy) = sin x cosh y + i cos x sinh y`.
+    pub fn sin(self) -> Point {
+        let Point(x, y) = self;
+        Point(x.sin() * y.cosh(), x.cos() * y.sinh())
+    }
+
+    /// Complex exponential, `exp(x + iy) = e^x (cos y + i sin y)`.
+    pub fn exp(self) -> Point {
+        let Point(x, y) = self;
+        let scale = x.exp();
+        Point(scale * y.cos(), scale * y.sin())
+    }
+
+    /// Principal complex logarithm, `ln |z| + i arg z` with `arg z` in `(-pi, pi]`. The branch
+    /// cut runs along the negative real axis, which itself gets `arg z = pi` even when its
+    /// imaginary part is `-0.0`.
+    pub fn ln(self) -> Point {
+        let Point(x, y) = self;
+        // adding zero turns -0.0 into 0.0, which keeps atan2 from returning -pi
+        Point(self.magnitude().ln(), (y + 0.0).atan2(x))
+    }
+
+    /// Raises to a whole power by repeated squaring, so that `powi(2)` is exactly `z * z`.
+    /// Negative powers divide 1 by the positive one, except that zero stays zero, the same as
+    /// with `powf`.
+    pub fn powi(self, n: i32) -> Point {
+        if n < 0 && self.0 == 0.0 && self.1 == 0.0 {
+            return self;
+        }
+        let mut result: Option<Point> = None;
+        let (mut base, mut rest) = (self, n.unsigned_abs());
+        while rest > 0 {
+            if rest & 1 == 1 {
+                result = Some(result.map_or(base, |r| r * base));
+            }
+            rest >>= 1;
+            if rest > 0 {
+                base = base * base;
+            }
+        }
+        let positive = result.unwrap_or(Point(1.0, 0.0));
+        if n < 0 {
+            Point(1.0, 0.0) / positive
+        } else {
+            positive
+        }
+    }
+
+    /// Raises to a real power as `exp(e ln z)`, on the principal branch.
+    pub fn powf(self, e: f64) -> Point {
+        if self.0 == 0.0 && self.1 == 0.0 {
+            return self;
+        }
+        (self.ln() * e).exp()
+    }
+}
+
+impl Add for Point {
+    type Output = Point;
+
+    fn add(self, Point(x, y): Point) -> Point {
+        Point(self.0 + x, self.1 + y)
+    }
+}
+
+impl Sub for Point {
+    type Output = Point;
+
+    fn sub(self, Point(x, y): Point) -> Point {
+        Point(self.0 - x, self.1 - y)
+    }
+}
+
+impl Mul for Point {
+    type Output = Point;
+
+    fn mul(self, Point(x, y): Point) -> Point {
+        let Point(u, v) = self;
+        Point(u * x - v * y, u * y + v * x)
+    }
+}
+
+impl Div for Point {
+    type Output = Point;
+
+    fn div(self, Point(x, y): Point) -> Point {
+        let Point(u, v) = self;
+        let scale = x * x + y * y;
+        Point((u * x + v * y) / scale, (v * x - u * y) / scale)
+    }
+}
+
+impl Mul<f64> for Point {
+    type Output = Point;
+
+    fn mul(self, scale: f64) -> Point {
+        Point(self.0 * scale, self.1 * scale)
+    }
+}
+
+/// Writes the point as a complex number, e.g. `0.32+0.043i`, which `from_str` reads back.
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = if self.1.is_sign_negative() { '-' } else { '+' };
+        write!(f, "{}{}{}i", self.0, sign, self.1.abs())
+    }
+}
+
+/// Reads a complex number like `0.32+0.043i`, `-1.5`, `2i` or `1e-3-i`. Spaces are ignored.
+impl FromStr for Point {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Point, String> {
+        let invalid = || format!("{:?} isn't a complex number like 0.32+0.043i", s);
+        let literal: String = s.chars().filter(|c| !c.is_whitespace()).collect();
+        let imaginary = match literal.strip_suffix('i') {
+            Some(imaginary) => imaginary,
+            None => return Ok(Point(literal.parse().map_err(|_| invalid())?, 0.0)),
+        };
+        // the imaginary part starts at the last sign that isn't the sign of an exponent
+        let split = imaginary
+            .char_indices()
+            .filter(|&(i, c)| {
+                (c == '+' || c == '-') && i > 0 && !imaginary[..i].ends_with(['e', 'E'])
+            })
+            .map(|(i, _)| i)
+            .next_back()
+            .unwrap_or(0);
+        let (real, imaginary) = imaginary.split_at(split);
+        let real = if real.is_empty() {
+            0.0
+        } else {
+            real.parse().map_err(|_| invalid())?
+        };
+        let imaginary = match imaginary {
+            "" | "+" => 1.0,
+            "-" => -1.0,
+            imaginary => imaginary.parse().map_err(|_| invalid())?,
+        };
+        Ok(Point(real, imaginary))
+    }
+}
+
+// Follows `iter` for at most `max_iter` steps and colors the point by how many of them stayed
+// close, as `color_escapes` would
+#[cfg(test)]
+fn choose_color<C: Copy>(
+    palette: &[C],
+    interior: C,
+    iter: impl Iterator<Item = Point>,
+    escaped: impl Fn(Point) -> bool,
+    max_iter: usize,
+) -> C {
+    escape(Point(0.0, 0.0), iter, escaped, max_iter).color(palette, interior, max_iter, 0)
+}
+
+/// How an orbit ended, as found by `sample_escapes`.
+#[derive(Copy, Clone, Debug)]
+pub struct EscapeResult {
+    /// How many steps stayed close before the orbit escaped, or `None` if it never did.
+    pub count: Option<usize>,
+    /// The last point reached: the first one outside for orbits that escaped, or the last one
+    /// followed for those that didn't. Orbits that ended without a step stay at the point
+    /// that was sampled.
+    pub last: Point,
+}
+
+impl EscapeResult {
+    /// The color for this count, scaled onto `palette` so a longer or shorter palette changes
+    /// only the colors, not which points are inside, then moved `shift` colors along it and
+    /// wrapped round at its end. Points that never escaped get `interior`.
+    pub fn color<C: Copy>(&self, palette: &[C], interior: C, max_iter: usize, shift: usize) -> C {
+        let count = match self.count {
+            Some(close) => close.saturating_sub(1),
+            None => return interior,
+        };
+        let index = if max_iter > 1 {
+            (count as f64 * (palette.len() - 1) as f64 / (max_iter - 1) as f64).round() as usize
+        } else {
+            0
+        };
+        palette[(index + shift) % palette.len()]
+    }
+
+    /// A fractional escape count that grows smoothly across the bands of whole counts, for an
+    /// orbit that raises `z` to `power` each step and escaped on leaving the disc of radius
+    /// `bailout`: `count - log(ln |z| / ln bailout) / log(power)`, between the whole count
+    /// less one and the whole count. The logarithm has to be to the base of the power for the
+    /// bands to meet, so higher powers of the Mandelbrot set need theirs passed in.
+    ///
+    /// Points that never escaped get `None`, and ones that overflowed get their whole count
+    /// less one, as nothing is known about how far out they went.
+    pub fn smooth_count(&self, power: f64, bailout: f64) -> Option<f64> {
+        let close = self.count? as f64;
+        let fraction = (self.last.magnitude().ln() / bailout.ln()).ln() / power.ln();
+        Some(
+            if fraction.is_finite() {
+                close - fraction.clamp(0.0, 1.0)
+            } else {
+                close - 1.0
+            }
+            .max(0.0),
+        )
     }
 }
 
-fn choose_color<C: Copy>(palette: impl Iterator<Item = C>, iter: impl Iterator<Item = Point>) -> C {
-    let mut palette = palette.peekable();
-    let first = *palette.peek().unwrap();
-    palette
-        .zip(iter)
-        .take_while(|(_, p)| Point::fairly_close(*p))
-        .fold(first, |_, (c, _)| c)
+// Follows `iter`, the orbit of `start`, for at most `max_iter` steps, stopping once it escapes
+// or ends early. An orbit that overflows has escaped too, whatever `escaped` makes of the NaNs.
+fn escape(
+    start: Point,
+    iter: impl Iterator<Item = Point>,
+    escaped: impl Fn(Point) -> bool,
+    max_iter: usize,
+) -> EscapeResult {
+    let mut close = 0;
+    let mut last = start;
+    for p in iter.take(max_iter) {
+        last = p;
+        if !p.is_finite() || escaped(p) {
+            break;
+        }
+        close += 1;
+    }
+    EscapeResult {
+        count: if close == max_iter { None } else { Some(close) },
+        last,
+    }
 }
 
 pub trait Generator {
     type Output: Iterator<Item = Point>;
     fn generate(&self, p: Point) -> Self::Output;
+
+    /// Whether an orbit that has reached `z` is on its way to infinity. By default that's once
+    /// it leaves the disc of radius 10.
+    fn escaped(&self, z: Point) -> bool {
+        !Point::fairly_close(z)
+    }
+
+    /// How far out an orbit is once `escaped` says it's gone, which smooth coloring measures
+    /// the last step against. By default that's `ESCAPE_RADIUS`.
+    fn bailout(&self) -> f64 {
+        ESCAPE_RADIUS
+    }
+
+    /// The orbit of `p` as `generate` gives it, with the derivative of each point with respect
+    /// to `p` alongside, which distance estimates and lighting need. Only generators whose
+    /// steps are smooth in `p` have one; the rest give `None`, the default.
+    fn generate_with_derivative(&self, _p: Point) -> Option<DerivativeOrbit<'_>> {
+        None
+    }
+}
+
+impl<G: Generator + ?Sized> Generator for Box<G> {
+    type Output = G::Output;
+
+    fn generate(&self, p: Point) -> Self::Output {
+        (**self).generate(p)
+    }
+
+    fn escaped(&self, z: Point) -> bool {
+        (**self).escaped(z)
+    }
+
+    fn bailout(&self) -> f64 {
+        (**self).bailout()
+    }
+
+    fn generate_with_derivative(&self, p: Point) -> Option<DerivativeOrbit<'_>> {
+        (**self).generate_with_derivative(p)
+    }
+}
+
+/// A generator whose concrete type is only known at runtime.
+pub type BoxedGenerator =
+    Box<dyn Generator<Output = Box<dyn Iterator<Item = Point>>> + Send + Sync>;
+
+/// An orbit with the derivative of each point with respect to the point sampled alongside it.
+pub type DerivativeOrbit<'a> = Box<dyn Iterator<Item = (Point, Point)> + 'a>;
+
+struct Erased<G>(G);
+
+impl<G: Generator> Generator for Erased<G>
+where
+    G::Output: 'static,
+{
+    type Output = Box<dyn Iterator<Item = Point>>;
+
+    fn generate(&self, p: Point) -> Self::Output {
+        Box::new(self.0.generate(p))
+    }
+
+    fn escaped(&self, z: Point) -> bool {
+        self.0.escaped(z)
+    }
+
+    fn bailout(&self) -> f64 {
+        self.0.bailout()
+    }
+
+    fn generate_with_derivative(&self, p: Point) -> Option<DerivativeOrbit<'_>> {
+        self.0.generate_with_derivative(p)
+    }
+}
+
+/// Erases the type of a generator so different fractals can be swapped at runtime.
+pub fn boxed<G: Generator + Send + Sync + 'static>(generator: G) -> BoxedGenerator
+where
+    G::Output: 'static,
+{
+    Box::new(Erased(generator))
+}
+
+/// How an orbit ended, along with the derivative of the point it ended at with respect to the
+/// point sampled, as found by `sample_derivatives`. Distance estimates and lighting work from
+/// these.
+#[derive(Copy, Clone, Debug)]
+pub struct DerivativeResult {
+    pub escape: EscapeResult,
+    /// `None` for generators that don't follow the derivative of their orbits.
+    pub derivative: Option<Point>,
+}
+
+// how far the embossed surface sticks up toward the light, which keeps the shaded side from
+// going completely black
+const LIGHT_HEIGHT: f64 = 1.5;
+
+impl DerivativeResult {
+    /// Estimates how far the point sampled is from the set, using `|z| ln |z| / 2 |z'|` at the
+    /// point `z` the orbit escaped to, with `z'` its derivative.
+    ///
+    /// Returns `None` for points that are still close after `max_iter` iterations, i.e.
+    /// points treated as inside the set, for generators without a derivative, and for orbits
+    /// whose derivative overflowed before they escaped.
+    pub fn distance(&self) -> Option<f64> {
+        let (_, derivative) = (self.escape.count?, self.derivative?);
+        let z = self.escape.last.magnitude();
+        let distance = z * z.ln() / (2.0 * derivative.magnitude());
+        Some(distance).filter(|distance| distance.is_finite() && *distance > 0.0)
+    }
+
+    /// How strongly the point glows from the nearby boundary of the set, fading as
+    /// `exp(-distance / width)` with the distance from `distance`, so 1.0 right at the
+    /// boundary and about 0.37 at `width` away.
+    ///
+    /// Points without a distance, such as those inside the set, don't glow and get 0.0.
+    pub fn glow(&self, width: f64) -> f64 {
+        self.distance()
+            .map_or(0.0, |distance| (-distance / width).exp())
+    }
+
+    /// Lights the set as if its boundary were embossed, with the light coming from
+    /// `light_angle` radians counter-clockwise from the positive real axis. The surface
+    /// normal points along `z / z'` at the point `z` the orbit escaped to.
+    ///
+    /// Returns a factor from 0.0 for fully shaded to 1.0 for fully lit. Points inside the set
+    /// and ones without a derivative aren't shaded and get 1.0.
+    pub fn light(&self, light_angle: f64) -> f64 {
+        let derivative = match (self.escape.count, self.derivative) {
+            (Some(_), Some(derivative)) => derivative,
+            _ => return 1.0,
+        };
+        let normal = self.escape.last / derivative;
+        let length = normal.magnitude();
+        if !length.is_finite() || length == 0.0 {
+            return 1.0;
+        }
+        let facing = (normal.0 * light_angle.cos() + normal.1 * light_angle.sin()) / length;
+        ((facing + LIGHT_HEIGHT) / (1.0 + LIGHT_HEIGHT)).max(0.0)
+    }
+}
+
+// follows the orbit of `p` like `escape_point`, keeping the derivative of the last point
+// reached as well, for the generators that have one
+fn escape_with_derivative<G: Generator>(
+    generator: &G,
+    p: Point,
+    max_iter: usize,
+) -> DerivativeResult {
+    let orbit = match generator.generate_with_derivative(p) {
+        Some(orbit) => orbit,
+        None => {
+            return DerivativeResult {
+                escape: escape_point(generator, p, max_iter),
+                derivative: None,
+            }
+        }
+    };
+    // `escape` stops at the point it ends on, so the last derivative seen belongs to it
+    let derivative = Cell::new(None);
+    let orbit = orbit.map(|(z, dz)| {
+        derivative.set(Some(dz));
+        z
+    });
+    let escape = escape(p, orbit, |z| generator.escaped(z), max_iter);
+    DerivativeResult {
+        escape,
+        derivative: derivative.get(),
+    }
+}
+
+/// Estimates how far `p` is from the set drawn by `generator`, as `DerivativeResult::distance`
+/// does for the orbit of `p`.
+pub fn distance_estimate<G: Generator>(generator: &G, p: Point, max_iter: usize) -> Option<f64> {
+    escape_with_derivative(generator, p, max_iter).distance()
+}
+
+/// How strongly `p` glows from the nearby boundary of the set drawn by `generator`, as
+/// `DerivativeResult::glow` has it.
+pub fn glow<G: Generator>(generator: &G, p: Point, max_iter: usize, width: f64) -> f64 {
+    escape_with_derivative(generator, p, max_iter).glow(width)
+}
+
+/// Lights `p` in the set drawn by `generator` as if its boundary were embossed, as
+/// `DerivativeResult::light` has it.
+pub fn lambert_light<G: Generator>(
+    generator: &G,
+    p: Point,
+    max_iter: usize,
+    light_angle: f64,
+) -> f64 {
+    escape_with_derivative(generator, p, max_iter).light(light_angle)
 }
 
 pub trait Palette {
     type Item: Copy;
     type Output: Iterator<Item = Self::Item>;
     fn get(&self) -> Self::Output;
+    fn interior(&self) -> Self::Item;
 }
 
-fn make_image<G: Generator, P: Palette>(generator: &G, palette: &P, p: Point) -> P::Item
-where
-    P::Item: Copy,
-{
-    choose_color(palette.get(), generator.generate(p))
+pub(crate) fn escape_point<G: Generator>(generator: &G, p: Point, max_iter: usize) -> EscapeResult {
+    escape(p, generator.generate(p), |z| generator.escaped(z), max_iter)
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Grid<C>(pub Vec<Vec<C>>);
 
+impl<C> Grid<C> {
+    /// Builds a grid from known rows, which must all be the same length.
+    pub fn from_rows(rows: Vec<Vec<C>>) -> Result<Grid<C>, String> {
+        if let Some(first) = rows.first() {
+            let width = first.len();
+            if let Some((i, row)) = rows.iter().enumerate().find(|(_, row)| row.len() != width) {
+                return Err(format!(
+                    "row {} has {} cells but row 0 has {}",
+                    i,
+                    row.len(),
+                    width
+                ));
+            }
+        }
+        Ok(Grid(rows))
+    }
+
+    /// Builds a grid by splitting a flat sequence of cells into rows of `width` cells.
+    pub fn from_flat(width: usize, cells: impl IntoIterator<Item = C>) -> Result<Grid<C>, String> {
+        if width == 0 {
+            return Err("rows must have at least one cell".to_owned());
+        }
+        let mut rows = vec![];
+        let mut row = Vec::with_capacity(width);
+        for c in cells {
+            row.push(c);
+            if row.len() == width {
+                rows.push(std::mem::replace(&mut row, Vec::with_capacity(width)));
+            }
+        }
+        if !row.is_empty() {
+            return Err(format!(
+                "{} cells left over after filling rows of {}",
+                row.len(),
+                width
+            ));
+        }
+        Ok(Grid(rows))
+    }
+}
+
+impl<C: Sync> Grid<C> {
+    /// Applies `f` to every cell in parallel, leaving this grid as it was.
+    pub fn par_map_ref<D: Send>(&self, f: impl Fn(&C) -> D + Sync) -> Grid<D> {
+        Grid(
+            self.0
+                .par_iter()
+                .map(|row| row.par_iter().map(&f).collect())
+                .collect(),
+        )
+    }
+}
+
+/// A rectangle of grid cells, or image pixels, with its lower left corner at (x, y).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub w: usize,
+    pub h: usize,
+}
+
+impl<C: Clone> Grid<C> {
+    /// Copies out the cells covered by `region`, which must lie within the grid.
+    pub fn region(&self, Rect { x, y, w, h }: Rect) -> Grid<C> {
+        Grid(
+            self.0[y..y + h]
+                .iter()
+                .map(|row| row[x..x + w].to_vec())
+                .collect(),
+        )
+    }
+
+    /// Copies out the `w` by `h` cells starting at column `x` of row `y`, or says why they
+    /// aren't all within the grid.
+    pub fn crop(&self, x: usize, y: usize, w: usize, h: usize) -> Result<Grid<C>, String> {
+        let height = self.0.len();
+        let width = self.0.first().map_or(0, Vec::len);
+        if x.checked_add(w).is_none_or(|right| right > width)
+            || y.checked_add(h).is_none_or(|top| top > height)
+        {
+            return Err(format!(
+                "{}x{} cells at ({}, {}) don't fit in a {}x{} grid",
+                w, h, x, y, width, height
+            ));
+        }
+        Ok(self.region(Rect { x, y, w, h }))
+    }
+}
+
+/// How the points of each pixel are laid out when supersampling.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SamplePattern {
+    /// An evenly spaced grid, which lines up with horizontal and vertical edges and so does
+    /// little for them.
+    Grid,
+    /// The same grid turned so that no two points share a row or a column of the pixel, like
+    /// the usual 4-point RGSS for a factor of 2, which smooths near-straight edges far better.
+    RotatedGrid,
+    /// The grid with each point moved to somewhere random in its cell, trading aliasing for
+    /// noise. The same seed is used every time, so renders stay reproducible.
+    Jittered,
+}
+
+// seeds the jittered pattern, so that every render of a view gets the same points
+pub(crate) const JITTER_SEED: u64 = 0x5eed;
+
+impl SamplePattern {
+    // The offset from the centre of a pixel, in pixels, of the point in column `i` and row `j`
+    // of the `factor` by `factor` points sampled for it. A single point is always the centre.
+    pub(crate) fn offset<R: Rng>(
+        self,
+        i: usize,
+        j: usize,
+        factor: usize,
+        rng: &mut R,
+    ) -> (f64, f64) {
+        if factor == 1 {
+            return (0.0, 0.0);
+        }
+        let n = factor as f64;
+        match self {
+            SamplePattern::Grid => ((i as f64 + 0.5) / n - 0.5, (j as f64 + 0.5) / n - 0.5),
+            SamplePattern::RotatedGrid => {
+                // split the pixel into factor^2 columns and rows, and give each point its own
+                let (x, y) = (i * factor + j, j * factor + (factor - 1 - i));
+                (
+                    (x as f64 + 0.5) / (n * n) - 0.5,
+                    (y as f64 + 0.5) / (n * n) - 0.5,
+                )
+            }
+            SamplePattern::Jittered => (
+                (i as f64 + rng.gen::<f64>()) / n - 0.5,
+                (j as f64 + rng.gen::<f64>()) / n - 0.5,
+            ),
+        }
+    }
+}
+
+impl FromStr for SamplePattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<SamplePattern, String> {
+        match s {
+            "grid" => Ok(SamplePattern::Grid),
+            "rotated" => Ok(SamplePattern::RotatedGrid),
+            "jittered" => Ok(SamplePattern::Jittered),
+            _ => Err(format!(
+                "unknown sample pattern {:?}, expected grid, rotated or jittered",
+                s
+            )),
+        }
+    }
+}
+
+// Swaps the coordinates of opposite corners where they're the wrong way round, so that a
+// backwards drag covers the same region as a forwards one, and panics if they don't span any
+// area.
+fn normalize(min: Point, max: Point) -> (Point, Point) {
+    let (x0, x1) = (min.0.min(max.0), min.0.max(max.0));
+    let (y0, y1) = (min.1.min(max.1), min.1.max(max.1));
+    assert!(
+        x0 < x1 && y0 < y1,
+        "{:?} to {:?} doesn't cover any area",
+        min,
+        max
+    );
+    (Point(x0, y0), Point(x1, y1))
+}
+
 impl Grid<Point> {
-    pub fn new(
-        col: usize,
-        row: usize,
-        Point(x_min, y_min): Point,
-        Point(x_max, y_max): Point,
-    ) -> Grid<Point> {
-        assert!(x_min < x_max);
-        assert!(y_min < y_max);
-        let y_spread = (y_max - y_min) / (row - 1) as f64;
-        let x_spread = (x_max - x_min) / (col - 1) as f64;
+    /// A `col` by `row` grid of points spread evenly from `min` to `max`, which may be any two
+    /// opposite corners.
+    pub fn new(col: usize, row: usize, min: Point, max: Point) -> Grid<Point> {
+        let (min, max) = normalize(min, max);
+        let viewport = Viewport {
+            min,
+            max,
+            width: col,
+            height: row,
+        };
         let mut rows = vec![];
         rows.reserve(row);
 
         for r in 0..row {
             let mut curr_row = vec![];
             curr_row.reserve(col);
-            let y = y_spread * r as f64 + y_min;
 
             for c in 0..col {
-                let x = x_spread * c as f64 + x_min;
-                curr_row.push(Point(x, y));
+                curr_row.push(viewport.pixel_to_point(c as f64, r as f64));
             }
 
             rows.push(curr_row);
@@ -72,21 +698,158 @@ impl Grid<Point> {
 
         Grid(rows)
     }
+
+    /// Like `new` with `factor` times as many points along each side, spread so that each
+    /// `factor` by `factor` block is centred on the point `new` would have put there. Used to
+    /// render big and then `downsample` for antialiasing.
+    pub fn supersampled(
+        col: usize,
+        row: usize,
+        min: Point,
+        max: Point,
+        factor: usize,
+    ) -> Grid<Point> {
+        Grid::supersampled_in_pattern(col, row, min, max, factor, SamplePattern::Grid)
+    }
+
+    /// Like `supersampled`, with the points of each block arranged in `pattern`. Each block
+    /// still covers just its own pixel, so `downsample` averages it the same way.
+    pub fn supersampled_in_pattern(
+        col: usize,
+        row: usize,
+        min: Point,
+        max: Point,
+        factor: usize,
+        pattern: SamplePattern,
+    ) -> Grid<Point> {
+        let (min, max) = normalize(min, max);
+        assert!(factor > 0);
+        let viewport = Viewport {
+            min,
+            max,
+            width: col,
+            height: row,
+        };
+        let mut rng = StdRng::seed_from_u64(JITTER_SEED);
+        Grid(
+            (0..row * factor)
+                .map(|r| {
+                    (0..col * factor)
+                        .map(|c| {
+                            let (x, y) = pattern.offset(c % factor, r % factor, factor, &mut rng);
+                            viewport
+                                .pixel_to_point((c / factor) as f64 + x, (r / factor) as f64 + y)
+                        })
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+
+    /// The same points turned `angle` radians anticlockwise about `center`, usually the middle
+    /// of the view, to sample it at an angle.
+    pub fn rotated_about(self, center: Point, angle: f64) -> Grid<Point> {
+        if angle == 0.0 {
+            return self;
+        }
+        self.par_map_ref(|&p| p.rotated_about(center, angle))
+    }
 }
 
-fn sample<G: Generator, P: Palette>(grid: &Grid<Point>, generator: &G, palette: &P) -> Grid<P::Item>
+// the palette's colors collected once, so each point can pick one by index
+fn palette_colors<P: Palette>(palette: &P) -> (Vec<P::Item>, P::Item) {
+    let colors: Vec<_> = palette.get().collect();
+    assert!(!colors.is_empty(), "palettes need at least one color");
+    (colors, palette.interior())
+}
+
+fn sample<G: Generator + Sync>(
+    grid: &Grid<Point>,
+    generator: &G,
+    max_iter: usize,
+    progress: &(dyn Fn(f64) + Sync),
+) -> Grid<EscapeResult> {
+    let rows = grid.0.len();
+    let finished = AtomicUsize::new(0);
+    Grid(
+        grid.0
+            .par_iter()
+            .map(|c| {
+                let row = c
+                    .par_iter()
+                    .map(|&p| escape_point(generator, p, max_iter))
+                    .collect();
+                let done = finished.fetch_add(1, Ordering::SeqCst) + 1;
+                progress(done as f64 / rows as f64);
+                row
+            })
+            .collect(),
+    )
+}
+
+/// Follows each point's orbit for at most `max_iter` steps and records how it ended, without
+/// picking any colors. Coloring the results with `color_escapes` gives the same colors as
+/// `draw`, and they can be colored again, with other palettes or shifts, without iterating
+/// anything.
+pub fn sample_escapes<G: Generator + Sync>(
+    grid: &Grid<Point>,
+    generator: &G,
+    max_iter: usize,
+) -> Grid<EscapeResult> {
+    sample(grid, generator, max_iter, &|_| ())
+}
+
+/// Follows each point's orbit like `sample_escapes`, keeping the derivative of the point each
+/// one ended at as well, so that a view can be lit or given a glow from the same pass it's
+/// colored from.
+pub fn sample_derivatives<G: Generator + Sync>(
+    grid: &Grid<Point>,
+    generator: &G,
+    max_iter: usize,
+) -> Grid<DerivativeResult> {
+    grid.par_map_ref(|&p| escape_with_derivative(generator, p, max_iter))
+}
+
+/// Colors results from `sample_escapes` with `palette`, with every color moved `shift` places
+/// along it.
+pub fn color_escapes<P: Palette>(
+    escapes: &Grid<EscapeResult>,
+    palette: &P,
+    max_iter: usize,
+    shift: usize,
+) -> Grid<P::Item>
 where
-    P::Item: Copy + Send,
-    G: Sync,
-    P: Sync,
+    P::Item: Send + Sync,
 {
+    let (colors, interior) = palette_colors(palette);
+    escapes.par_map_ref(|escape| escape.color(&colors, interior, max_iter, shift))
+}
+
+/// Finds the edges between results from `sample_escapes`: the points with a neighbour above,
+/// below or to either side that took more than `threshold` steps longer to escape, counting
+/// points that never escaped as taking `max_iter`. Only the side that escaped sooner is
+/// marked, so edges come out a point wide. With a threshold of 0 that traces every band of
+/// color, and with one just under `max_iter` only the outline of the set.
+pub fn boundary(escapes: &Grid<EscapeResult>, max_iter: usize, threshold: usize) -> Grid<bool> {
+    let count = |r: usize, c: usize| escapes.0[r][c].count.unwrap_or(max_iter);
+    let rows = escapes.0.len();
     Grid(
-        grid.0
-            .clone()
+        (0..rows)
             .into_par_iter()
-            .map(|c| {
-                c.into_par_iter()
-                    .map(|p| make_image(generator, palette, p))
+            .map(|r| {
+                let cols = escapes.0[r].len();
+                (0..cols)
+                    .map(|c| {
+                        let here = count(r, c);
+                        let below = r.checked_sub(1).map(|r| (r, c));
+                        let left = c.checked_sub(1).map(|c| (r, c));
+                        let above = Some((r + 1, c)).filter(|&(r, _)| r < rows);
+                        let right = Some((r, c + 1)).filter(|&(_, c)| c < cols);
+                        [below, left, above, right]
+                            .iter()
+                            .flatten()
+                            .any(|&(r, c)| count(r, c) > here + threshold)
+                    })
                     .collect()
             })
             .collect(),
@@ -96,29 +859,862 @@ where
 pub trait Renderer {
     type Item: Copy;
     fn render(&mut self, grid: Grid<Self::Item>);
+
+    /// Called once the last grid has been rendered, to flush anything the renderer buffers and
+    /// report what went wrong writing it out. Does nothing unless a renderer needs it to.
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// How many colors this renderer, and any it passes them on to, have had a channel clamped
+    /// into range so far. Only the ones that blend or shade colors have any to count.
+    fn clipped(&self) -> usize {
+        0
+    }
 }
 
+/// Samples every point in `points`, following each orbit for at most `max_iter` steps, and
+/// hands the colored grid to `renderer`, finishing it afterwards.
 pub fn draw<G: Generator, P: Palette, R: Renderer<Item = P::Item>>(
     generator: &G,
     palette: &P,
+    max_iter: usize,
     renderer: &mut R,
     points: &Grid<Point>,
-) where
+) -> io::Result<()>
+where
     G: Sync,
     P: Sync,
-    P::Item: Send,
+    P::Item: Send + Sync,
 {
-    renderer.render(sample(points, generator, palette))
+    let escapes = sample(points, generator, max_iter, &|_| ());
+    renderer.render(color_escapes(&escapes, palette, max_iter, 0));
+    renderer.finish()
+}
+
+/// Like `draw`, but samples one point at a time on the calling thread. The output is the same
+/// as `draw`'s, which makes this easier to step through and to test against.
+pub fn draw_sequential<G: Generator, P: Palette, R: Renderer<Item = P::Item>>(
+    generator: &G,
+    palette: &P,
+    max_iter: usize,
+    renderer: &mut R,
+    points: &Grid<Point>,
+) -> io::Result<()> {
+    let (colors, interior) = palette_colors(palette);
+    renderer.render(Grid(
+        points
+            .0
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&p| {
+                        escape_point(generator, p, max_iter).color(&colors, interior, max_iter, 0)
+                    })
+                    .collect()
+            })
+            .collect(),
+    ));
+    renderer.finish()
+}
+
+/// Like `draw`, but calls `progress` with the fraction of rows sampled so far as each one
+/// finishes. Rows finish on several threads at once, so the calls can come from any of them.
+pub fn draw_with_progress<G, P, R>(
+    generator: &G,
+    palette: &P,
+    max_iter: usize,
+    renderer: &mut R,
+    points: &Grid<Point>,
+    progress: impl Fn(f64) + Sync,
+) -> io::Result<()>
+where
+    G: Generator + Sync,
+    P: Palette + Sync,
+    P::Item: Send + Sync,
+    R: Renderer<Item = P::Item>,
+{
+    let escapes = sample(points, generator, max_iter, &progress);
+    renderer.render(color_escapes(&escapes, palette, max_iter, 0));
+    renderer.finish()
+}
+
+/// Like `draw`, but only samples the points within `region`. The renderer is handed just
+/// those cells, so it should be one that writes at the region's corner, such as
+/// `RGBRenderer::at`, to leave the rest of the output untouched.
+pub fn draw_region<G, P, R>(
+    generator: &G,
+    palette: &P,
+    max_iter: usize,
+    renderer: &mut R,
+    points: &Grid<Point>,
+    region: Rect,
+) -> io::Result<()>
+where
+    G: Generator + Sync,
+    P: Palette + Sync,
+    P::Item: Send + Sync,
+    R: Renderer<Item = P::Item>,
+{
+    draw(
+        generator,
+        palette,
+        max_iter,
+        renderer,
+        &points.region(region),
+    )
+}
+
+/// A finished square of a render from `draw_tiles`, with its lower left corner at column `x`
+/// and row `y` of the full grid.
+#[derive(Debug)]
+pub struct Tile<C> {
+    pub x: usize,
+    pub y: usize,
+    pub colors: Grid<C>,
+}
+
+/// Like `draw`, but hands back the render in tiles of up to `tile_size` by `tile_size` cells
+/// as they finish, so that partial results can be shown or sent on before the whole grid is
+/// done. Each band of tiles across the grid is sampled in parallel and then yielded from
+/// left to right, bottom band first; nothing is sampled until the iterator is advanced.
+pub fn draw_tiles<'a, G, P>(
+    generator: &'a G,
+    palette: &P,
+    max_iter: usize,
+    points: &'a Grid<Point>,
+    tile_size: usize,
+) -> impl Iterator<Item = Tile<P::Item>> + 'a
+where
+    G: Generator + Sync,
+    P: Palette,
+    P::Item: Send + Sync + 'a,
+{
+    assert!(tile_size > 0, "tiles need at least one cell");
+    let (colors, interior) = palette_colors(palette);
+    let height = points.0.len();
+    let width = points.0.first().map_or(0, Vec::len);
+    (0..height).step_by(tile_size).flat_map(move |y| {
+        let h = tile_size.min(height - y);
+        let starts: Vec<_> = (0..width).step_by(tile_size).collect();
+        let colors = &colors;
+        let tiles: Vec<_> = starts
+            .into_par_iter()
+            .map(|x| {
+                let w = tile_size.min(width - x);
+                let cells = points.0[y..y + h]
+                    .iter()
+                    .map(|row| {
+                        row[x..x + w]
+                            .iter()
+                            .map(|&p| {
+                                escape_point(generator, p, max_iter)
+                                    .color(colors, interior, max_iter, 0)
+                            })
+                            .collect()
+                    })
+                    .collect();
+                Tile {
+                    x,
+                    y,
+                    colors: Grid(cells),
+                }
+            })
+            .collect();
+        tiles
+    })
 }
 
 #[test]
 fn color_tests() {
-    for (i, expected) in [0, 0, 1, 2, 3, 3].iter().enumerate() {
+    let still = |i| {
         let mut points = vec![Point(0.0, 0.0); i];
         points.push(Point(8.0, 8.0));
+        points.into_iter()
+    };
+    let escaped = |p| !Point::fairly_close(p);
+    // with as many iterations as colors, each count gets its own color
+    for (i, expected) in [0, 0, 1, 2, -1, -1].iter().enumerate() {
         assert_eq!(
-            expected,
-            choose_color([0, 1, 2, 3].iter(), points.into_iter())
+            *expected,
+            choose_color(&[0, 1, 2, 3], -1, still(i), escaped, 4)
         );
     }
+    // doubling the iterations keeps the same colors but spreads them over twice the counts
+    for (i, expected) in [0, 0, 0, 1, 1, 2, 2, 3, -1].iter().enumerate() {
+        assert_eq!(
+            *expected,
+            choose_color(&[0, 1, 2, 3], -1, still(i), escaped, 8)
+        );
+    }
+    // and a longer palette only changes which colors the counts land on
+    for (i, expected) in [0, 0, 2, 4, -1].iter().enumerate() {
+        assert_eq!(
+            *expected,
+            choose_color(&[0, 1, 2, 3, 4, 5, 6], -1, still(i), escaped, 4)
+        );
+    }
+}
+
+#[test]
+fn grid_from_rows_test() {
+    assert_eq!(
+        Grid::from_rows(vec![vec![1, 2], vec![3, 4]]).unwrap().0,
+        vec![vec![1, 2], vec![3, 4]]
+    );
+    assert!(Grid::from_rows(vec![vec![1, 2], vec![3]]).is_err());
+    assert_eq!(
+        Grid::from_flat(2, 1..=4).unwrap().0,
+        vec![vec![1, 2], vec![3, 4]]
+    );
+    assert!(Grid::from_flat(2, 1..=3).is_err());
+}
+
+#[test]
+fn grid_region_test() {
+    let grid = Grid::from_flat(3, 0..9).unwrap();
+    let region = Rect {
+        x: 1,
+        y: 1,
+        w: 2,
+        h: 2,
+    };
+    assert_eq!(grid.region(region).0, vec![vec![4, 5], vec![7, 8]]);
+}
+
+#[test]
+fn reversed_grid_test() {
+    let (min, max) = (Point(-2.0, -1.0), Point(1.0, 1.0));
+    let expected = Grid::new(4, 3, min, max);
+    for &(a, b) in &[
+        (max, min),
+        (Point(1.0, -1.0), Point(-2.0, 1.0)),
+        (Point(-2.0, 1.0), Point(1.0, -1.0)),
+    ] {
+        let grid = Grid::new(4, 3, a, b);
+        assert_eq!(format!("{:?}", grid), format!("{:?}", expected));
+        let supersampled = Grid::supersampled(4, 3, a, b, 2);
+        assert_eq!(
+            format!("{:?}", supersampled),
+            format!("{:?}", Grid::supersampled(4, 3, min, max, 2))
+        );
+    }
+}
+
+#[test]
+#[should_panic(expected = "doesn't cover any area")]
+fn flat_grid_test() {
+    Grid::new(4, 3, Point(-2.0, 1.0), Point(1.0, 1.0));
+}
+
+#[test]
+fn supersampled_grid_test() {
+    let (min, max) = (Point(-2.0, -1.0), Point(2.0, 1.0));
+    let coarse = Grid::new(5, 3, min, max);
+    let same = Grid::supersampled(5, 3, min, max, 1);
+    assert_eq!(format!("{:?}", same.0), format!("{:?}", coarse.0));
+
+    let fine = Grid::supersampled(5, 3, min, max, 2);
+    assert_eq!((fine.0.len(), fine.0[0].len()), (6, 10));
+    for (r, row) in coarse.0.iter().enumerate() {
+        for (c, &Point(x, y)) in row.iter().enumerate() {
+            let block = [(0, 0), (0, 1), (1, 0), (1, 1)].map(|(i, j)| fine.0[2 * r + i][2 * c + j]);
+            let mean_x = block.iter().map(|p| p.0).sum::<f64>() / 4.0;
+            let mean_y = block.iter().map(|p| p.1).sum::<f64>() / 4.0;
+            assert!((mean_x - x).abs() < 1e-12 && (mean_y - y).abs() < 1e-12);
+        }
+    }
+}
+
+#[test]
+fn sample_pattern_test() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let offsets = |pattern: SamplePattern, factor, rng: &mut StdRng| {
+        let mut offsets = vec![];
+        for j in 0..factor {
+            for i in 0..factor {
+                offsets.push(pattern.offset(i, j, factor, rng));
+            }
+        }
+        offsets
+    };
+    // the usual RGSS pattern
+    assert_eq!(
+        offsets(SamplePattern::RotatedGrid, 2, &mut rng),
+        vec![
+            (-0.375, -0.125),
+            (0.125, -0.375),
+            (-0.125, 0.375),
+            (0.375, 0.125)
+        ]
+    );
+    for &pattern in &[
+        SamplePattern::Grid,
+        SamplePattern::RotatedGrid,
+        SamplePattern::Jittered,
+    ] {
+        assert_eq!(offsets(pattern, 1, &mut rng), vec![(0.0, 0.0)]);
+        for &factor in &[2, 3, 4] {
+            let points = offsets(pattern, factor, &mut rng);
+            assert!(points
+                .iter()
+                .all(|&(x, y)| (-0.5..0.5).contains(&x) && (-0.5..0.5).contains(&y)));
+            if pattern == SamplePattern::RotatedGrid {
+                // no two points share a column or a row
+                let mut xs: Vec<_> = points.iter().map(|p| p.0).collect();
+                let mut ys: Vec<_> = points.iter().map(|p| p.1).collect();
+                xs.sort_by(f64::total_cmp);
+                ys.sort_by(f64::total_cmp);
+                xs.dedup();
+                ys.dedup();
+                assert_eq!((xs.len(), ys.len()), (factor * factor, factor * factor));
+            }
+        }
+    }
+    assert_eq!(
+        "rotated".parse::<SamplePattern>(),
+        Ok(SamplePattern::RotatedGrid)
+    );
+    assert!("poisson".parse::<SamplePattern>().is_err());
+
+    // the grid pattern is what `supersampled` uses
+    let (min, max) = (Point(-2.0, -1.0), Point(2.0, 1.0));
+    assert_eq!(
+        format!("{:?}", Grid::supersampled(5, 3, min, max, 3)),
+        format!(
+            "{:?}",
+            Grid::supersampled_in_pattern(5, 3, min, max, 3, SamplePattern::Grid)
+        )
+    );
+}
+
+#[test]
+fn boundary_test() {
+    let escape = |count| EscapeResult {
+        count,
+        last: Point(0.0, 0.0),
+    };
+    let escapes = Grid(vec![
+        vec![escape(Some(1)), escape(Some(2)), escape(Some(5))],
+        vec![escape(Some(1)), escape(Some(3)), escape(None)],
+    ]);
+    let edges = |threshold| format!("{:?}", boundary(&escapes, 10, threshold).0);
+    // every step up in count is an edge on the lower side
+    assert_eq!(
+        edges(0),
+        format!(
+            "{:?}",
+            vec![vec![true, true, true], vec![true, true, false]]
+        )
+    );
+    // the jump from 2 to 5, and every one into the set
+    assert_eq!(
+        edges(2),
+        format!(
+            "{:?}",
+            vec![vec![false, true, true], vec![false, true, false]]
+        )
+    );
+    assert_eq!(
+        edges(9),
+        format!("{:?}", vec![vec![false; 3], vec![false; 3]])
+    );
+}
+
+#[test]
+fn draw_tiles_test() {
+    use crate::mandelbrot::Mandelbrot;
+    let mandelbrot = Mandelbrot::new(Point(0.0, 0.0));
+    let points = Grid::new(7, 5, Point(-2.0, -1.0), Point(0.5, 1.0));
+    let mut whole = vec![];
+    draw(&mandelbrot, &Digits, 10, &mut whole, &points).unwrap();
+
+    let tiles: Vec<_> = draw_tiles(&mandelbrot, &Digits, 10, &points, 3).collect();
+    let corners: Vec<_> = tiles.iter().map(|tile| (tile.x, tile.y)).collect();
+    assert_eq!(
+        corners,
+        vec![(0, 0), (3, 0), (6, 0), (0, 3), (3, 3), (6, 3)]
+    );
+    let mut assembled = vec![vec![0; 7]; 5];
+    for Tile { x, y, colors } in tiles {
+        assert!(colors.0.len() <= 3 && colors.0.iter().all(|row| row.len() <= 3));
+        for (r, row) in colors.0.into_iter().enumerate() {
+            for (c, color) in row.into_iter().enumerate() {
+                assembled[y + r][x + c] = color;
+            }
+        }
+    }
+    assert_eq!(assembled, whole);
+    assert_eq!(
+        draw_tiles(&mandelbrot, &Digits, 10, &Grid(vec![]), 4).count(),
+        0
+    );
+}
+
+#[test]
+fn grid_crop_test() {
+    let grid = Grid::from_flat(3, 0..6).unwrap();
+    assert_eq!(
+        grid.crop(1, 0, 2, 2).unwrap().0,
+        vec![vec![1, 2], vec![4, 5]]
+    );
+    assert_eq!(grid.crop(0, 0, 3, 2).unwrap().0, grid.0);
+    assert_eq!(grid.crop(3, 2, 0, 0).unwrap().0, Vec::<Vec<i32>>::new());
+    assert_eq!(
+        grid.crop(2, 0, 2, 1).err().unwrap(),
+        "2x1 cells at (2, 0) don't fit in a 3x2 grid"
+    );
+    assert!(grid.crop(0, 1, 1, 2).is_err());
+    assert!(grid.crop(usize::MAX, 0, 2, 1).is_err());
+}
+
+#[cfg(test)]
+struct Still;
+
+#[cfg(test)]
+impl Generator for Still {
+    type Output = std::iter::Repeat<Point>;
+
+    fn generate(&self, p: Point) -> Self::Output {
+        std::iter::repeat(p)
+    }
+}
+
+#[cfg(test)]
+struct Digits;
+
+#[cfg(test)]
+impl Palette for Digits {
+    type Item = u8;
+    type Output = std::ops::Range<u8>;
+
+    fn get(&self) -> Self::Output {
+        0..10
+    }
+
+    fn interior(&self) -> u8 {
+        10
+    }
+}
+
+#[cfg(test)]
+impl Renderer for Vec<Vec<u8>> {
+    type Item = u8;
+
+    fn render(&mut self, grid: Grid<u8>) {
+        *self = grid.0;
+    }
+}
+
+#[test]
+fn draw_with_progress_test() {
+    let reports = std::sync::Mutex::new(vec![]);
+    let mut rendered = vec![];
+    let points = Grid::new(4, 3, Point(-1.0, -1.0), Point(1.0, 1.0));
+    draw_with_progress(&Still, &Digits, 10, &mut rendered, &points, |done| {
+        reports.lock().unwrap().push(done)
+    })
+    .unwrap();
+    assert_eq!(rendered, vec![vec![10; 4]; 3]);
+    let mut reports = reports.into_inner().unwrap();
+    reports.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    assert_eq!(reports, vec![1.0 / 3.0, 2.0 / 3.0, 1.0]);
+}
+
+#[test]
+fn draw_sequential_test() {
+    use crate::mandelbrot::Mandelbrot;
+    let mandelbrot = Mandelbrot::new(Point(0.0, 0.0));
+    let points = Grid::new(40, 30, Point(-2.0, -1.25), Point(0.5, 1.25));
+    let (mut parallel, mut sequential) = (vec![], vec![]);
+    draw(&mandelbrot, &Digits, 10, &mut parallel, &points).unwrap();
+    draw_sequential(&mandelbrot, &Digits, 10, &mut sequential, &points).unwrap();
+    assert_eq!(parallel, sequential);
+    // the view covers points inside the set and points that escape at different speeds
+    let mut seen: Vec<_> = sequential.into_iter().flatten().collect();
+    seen.sort_unstable();
+    seen.dedup();
+    assert!(seen.len() > 3 && seen.contains(&10), "{:?}", seen);
+}
+
+#[test]
+fn smooth_count_test() {
+    let escape = |count, last| EscapeResult {
+        count: Some(count),
+        last,
+    };
+    // with a bailout of e, power 2 is the classic n + 1 - log2(ln |z|), for n one less than
+    // the steps that stayed close
+    let z = Point(3.0, 4.0);
+    let classic = 6.0 + 1.0 - (5.0f64).ln().ln() / 2.0f64.ln();
+    let smooth = escape(7, z).smooth_count(2.0, std::f64::consts::E).unwrap();
+    assert!((smooth - classic).abs() < 1e-12, "{} {}", smooth, classic);
+    // just past the bailout it's the whole count, and at bailout^power one less, for any power
+    for &power in &[2.0, 3.0, 5.5] {
+        let at = |r: f64| escape(4, Point(r, 0.0)).smooth_count(power, 10.0).unwrap();
+        assert!((at(10.0) - 4.0).abs() < 1e-12);
+        assert!((at(10f64.powf(power)) - 3.0).abs() < 1e-12);
+    }
+    let overflowed = escape(4, Point(f64::INFINITY, 0.0));
+    assert_eq!(overflowed.smooth_count(2.0, 10.0), Some(3.0));
+    let inside = EscapeResult {
+        count: None,
+        last: z,
+    };
+    assert_eq!(inside.smooth_count(2.0, 10.0), None);
+}
+
+#[test]
+fn color_escapes_test() {
+    use crate::mandelbrot::Mandelbrot;
+    let mandelbrot = Mandelbrot::new(Point(0.0, 0.0));
+    let points = Grid::new(20, 15, Point(-2.0, -1.25), Point(0.5, 1.25));
+    let mut drawn = vec![];
+    draw(&mandelbrot, &Digits, 10, &mut drawn, &points).unwrap();
+    let escapes = sample_escapes(&points, &mandelbrot, 10);
+    assert_eq!(color_escapes(&escapes, &Digits, 10, 0).0, drawn);
+    for escape in escapes.0.iter().flatten() {
+        let Point(x, y) = escape.last;
+        assert_eq!(escape.count.is_some(), x * x + y * y >= 100.0);
+    }
+    // shifting moves escaped points along the palette and leaves the inside alone
+    let shifted = color_escapes(&escapes, &Digits, 10, 3).0;
+    for (&before, &after) in drawn.iter().flatten().zip(shifted.iter().flatten()) {
+        let expected = if before == 10 { 10 } else { (before + 3) % 10 };
+        assert_eq!(after, expected);
+    }
+}
+
+#[test]
+fn par_map_ref_test() {
+    let grid = Grid(vec![vec![1, 2], vec![3, 4]]);
+    assert_eq!(
+        grid.par_map_ref(|c| c * 10).0,
+        vec![vec![10, 20], vec![30, 40]]
+    );
+    assert_eq!(
+        grid.par_map_ref(|c| c % 2 == 0).0,
+        vec![vec![false, true], vec![false, true]]
+    );
+}
+
+#[test]
+fn distance_estimate_test() {
+    use crate::mandelbrot::Mandelbrot;
+    let mandelbrot = Mandelbrot::new(Point(0.0, 0.0));
+    assert_eq!(distance_estimate(&mandelbrot, Point(0.0, 0.0), 100), None);
+    // the set reaches 0.25 along the positive real axis
+    let estimate = distance_estimate(&mandelbrot, Point(2.0, 0.0), 100).unwrap();
+    assert!(
+        estimate > 1.75 / 4.0 && estimate < 1.75 * 4.0,
+        "{}",
+        estimate
+    );
+    assert!(distance_estimate(&mandelbrot, Point(0.3, 0.0), 100).unwrap() < estimate);
+    // and -2 along the negative one, which holds up however close the point gets
+    let estimate = distance_estimate(&mandelbrot, Point(-2.0 - 1e-12, 0.0), 100).unwrap();
+    assert!(
+        estimate > 1e-12 / 4.0 && estimate < 1e-12 * 4.0,
+        "{}",
+        estimate
+    );
+    // the Burning Ship's steps aren't smooth, so it has no derivative to estimate from
+    let ship = crate::burning_ship::BurningShip::new();
+    assert_eq!(distance_estimate(&ship, Point(2.0, 0.0), 100), None);
+}
+
+#[test]
+fn lambert_light_test() {
+    use crate::mandelbrot::Mandelbrot;
+    let mandelbrot = Mandelbrot::new(Point(0.0, 0.0));
+    assert_eq!(lambert_light(&mandelbrot, Point(0.0, 0.0), 100, 0.0), 1.0);
+    // right of the set the surface faces along the positive real axis
+    let lit = lambert_light(&mandelbrot, Point(2.0, 0.0), 100, 0.0);
+    let shaded = lambert_light(&mandelbrot, Point(2.0, 0.0), 100, std::f64::consts::PI);
+    assert!((lit - 1.0).abs() < 1e-3, "{}", lit);
+    assert!((shaded - 0.5 / 2.5).abs() < 1e-3, "{}", shaded);
+}
+
+#[test]
+fn glow_test() {
+    use crate::mandelbrot::Mandelbrot;
+    let mandelbrot = Mandelbrot::new(Point(0.0, 0.0));
+    assert_eq!(glow(&mandelbrot, Point(0.0, 0.0), 100, 0.1), 0.0);
+    let near = glow(&mandelbrot, Point(0.26, 0.0), 100, 0.1);
+    let far = glow(&mandelbrot, Point(1.0, 1.0), 100, 0.1);
+    assert!(near > 0.5 && far < near && far > 0.0, "{} {}", near, far);
+    // an escaped orbit whose derivative overflowed has no distance to glow from
+    let overflowed = DerivativeResult {
+        escape: EscapeResult {
+            count: Some(3),
+            last: Point(20.0, 0.0),
+        },
+        derivative: Some(Point(f64::INFINITY, 0.0)),
+    };
+    assert_eq!(overflowed.distance(), None);
+    assert_eq!(overflowed.glow(0.1), 0.0);
+}
+
+#[test]
+fn transcendental_test() {
+    use std::f64::consts::PI;
+    let close =
+        |Point(x, y): Point, Point(u, v): Point| (x - u).abs() < 1e-12 && (y - v).abs() < 1e-12;
+    assert!(close(Point(0.0, 0.0).exp(), Point(1.0, 0.0)));
+    // Euler's identity, e^(i pi) = -1
+    assert!(close(Point(0.0, PI).exp(), Point(-1.0, 0.0)));
+    assert!(close(Point(0.0, 0.0).cos(), Point(1.0, 0.0)));
+    assert!(close(Point(PI / 2.0, 0.0).sin(), Point(1.0, 0.0)));
+    // sin^2 + cos^2 = 1 off the real axis too
+    let z = Point(0.7, -1.3);
+    let (s, c) = (z.sin(), z.cos());
+    assert!(close(s * s + c * c, Point(1.0, 0.0)));
+}
+
+#[test]
+fn overflow_escapes_test() {
+    // comparisons with NaN are false, so a real part bailout alone would never stop this orbit
+    let real_bailout = |Point(x, _): Point| x > 50.0;
+    let overflowing = Point(1e300, 1e300) * 1e300;
+    let orbit = [Point(1.0, 0.0), Point(1.0, 1e300)]
+        .iter()
+        .cloned()
+        .chain(std::iter::repeat(overflowing * overflowing));
+    let palette: Vec<_> = (0..10).collect();
+    assert_eq!(choose_color(&palette, -1, orbit, real_bailout, 10), 1);
+}
+
+#[test]
+fn parse_point_test() {
+    let parse = |s: &str| s.parse::<Point>().map(|Point(x, y)| (x, y));
+    assert_eq!(parse("0.32+0.043i"), Ok((0.32, 0.043)));
+    assert_eq!(parse(" -0.8 - 0.156 i "), Ok((-0.8, -0.156)));
+    assert_eq!(parse("-1.5"), Ok((-1.5, 0.0)));
+    assert_eq!(parse("2i"), Ok((0.0, 2.0)));
+    assert_eq!(parse("-i"), Ok((0.0, -1.0)));
+    assert_eq!(parse("1e-3-i"), Ok((1e-3, -1.0)));
+    assert_eq!(parse("1+2e-3i"), Ok((1.0, 2e-3)));
+    for invalid in &["", "i+", "0.3+0.2j", "one+twoi", "1+2+3i"] {
+        assert!(parse(invalid).is_err(), "{}", invalid);
+    }
+    let c = Point(-0.7269, 0.1889);
+    assert_eq!(parse(&c.to_string()), Ok((c.0, c.1)));
+    assert_eq!(Point(0.25, -0.5).to_string(), "0.25-0.5i");
+}
+
+#[test]
+fn next_test() {
+    // (3 + 4i)^2 + (1 + 2i) = 9 + 24i - 16 + 1 + 2i
+    let Point(x, y) = Point::next(Point(1.0, 2.0), Point(3.0, 4.0));
+    assert_eq!((x, y), (-6.0, 26.0));
+    // starting from z = 0, the first step lands on c
+    let Point(x, y) = Point::next(Point(-0.75, 0.1), Point(0.0, 0.0));
+    assert_eq!((x, y), (-0.75, 0.1));
+    // i^2 - 1 = -2, and -2 goes to 4 - 2 = 2, then 2 stays put
+    let c = Point(-2.0, 0.0);
+    let Point(x, y) = Point::next(c, Point::next(c, Point(0.0, 0.0)));
+    assert_eq!((x, y), (2.0, 0.0));
+    let Point(x, y) = Point::next(c, Point(2.0, 0.0));
+    assert_eq!((x, y), (2.0, 0.0));
+}
+
+#[test]
+fn arithmetic_test() {
+    let close =
+        |Point(x, y): Point, Point(u, v): Point| (x - u).abs() < 1e-12 && (y - v).abs() < 1e-12;
+    let Point(x, y) = Point(1.5, -2.0) + Point(0.5, 3.0);
+    assert_eq!((x, y), (2.0, 1.0));
+    let Point(x, y) = Point(1.5, -2.0) - Point(0.5, 3.0);
+    assert_eq!((x, y), (1.0, -5.0));
+    // (1 + 2i)(3 - i) = 3 - i + 6i - 2i^2
+    let Point(x, y) = Point(1.0, 2.0) * Point(3.0, -1.0);
+    assert_eq!((x, y), (5.0, 5.0));
+    let Point(x, y) = Point(0.0, 1.0) * Point(0.0, 1.0);
+    assert_eq!((x, y), (-1.0, 0.0));
+    let Point(x, y) = Point(2.0, -3.0) * 0.5;
+    assert_eq!((x, y), (1.0, -1.5));
+    // multiplying by zero gives zero, either way round and by a real zero too
+    for zero in [
+        Point(2.0, 3.0) * Point(0.0, 0.0),
+        Point(0.0, 0.0) * Point(2.0, 3.0),
+    ] {
+        assert_eq!(zero.magnitude_squared(), 0.0);
+    }
+    assert_eq!((Point(2.0, 3.0) * 0.0).magnitude_squared(), 0.0);
+    // dividing undoes multiplying
+    assert!(close(Point(5.0, 5.0) / Point(3.0, -1.0), Point(1.0, 2.0)));
+    assert!(close(Point(1.0, 0.0) / Point(0.0, 1.0), Point(0.0, -1.0)));
+}
+
+#[test]
+fn conj_test() {
+    let z = Point(3.0, 4.0);
+    let Point(x, y) = z.conj();
+    assert_eq!((x, y), (3.0, -4.0));
+    // z times its conjugate is |z|^2, with nothing left over
+    let Point(x, y) = z * z.conj();
+    assert_eq!((x, y), (25.0, 0.0));
+    let Point(x, y) = z.conj().conj();
+    assert_eq!((x, y), (3.0, 4.0));
+}
+
+#[test]
+fn exp_ln_test() {
+    use std::f64::consts::{E, FRAC_PI_2, PI};
+    let close =
+        |Point(x, y): Point, Point(u, v): Point| (x - u).abs() < 1e-12 && (y - v).abs() < 1e-12;
+    assert!(close(Point(0.0, 0.0).exp(), Point(1.0, 0.0)));
+    assert!(close(Point(1.0, 0.0).exp(), Point(E, 0.0)));
+    // e^(i pi) = -1 and e^(i pi / 2) = i
+    assert!(close(Point(0.0, PI).exp(), Point(-1.0, 0.0)));
+    assert!(close(Point(0.0, FRAC_PI_2).exp(), Point(0.0, 1.0)));
+    assert!(close(Point(1.0, 0.0).ln(), Point(0.0, 0.0)));
+    assert!(close(Point(0.0, 1.0).ln(), Point(0.0, FRAC_PI_2)));
+    assert!(close(Point(0.0, -1.0).ln(), Point(0.0, -FRAC_PI_2)));
+    assert!(close(Point(-E, 0.0).ln(), Point(1.0, PI)));
+    // just above the branch cut the argument is nearly pi, and just below it nearly -pi, but
+    // exp takes both back to where they started
+    let (above, below) = (Point(-1.0, 1e-15), Point(-1.0, -1e-15));
+    assert!(close(above.ln(), Point(0.0, PI - 1e-15)));
+    assert!(close(below.ln(), Point(0.0, -PI + 1e-15)));
+    assert!(close(above.ln().exp(), Point(-1.0, 1e-15)));
+    assert!(close(below.ln().exp(), Point(-1.0, -1e-15)));
+    // i^2 on the principal branch
+    assert!(close(Point(0.0, 1.0).powf(2.0), Point(-1.0, 0.0)));
+    let Point(x, y) = Point(0.0, 0.0).powf(2.5);
+    assert_eq!((x, y), (0.0, 0.0));
+}
+
+#[test]
+fn cos_sin_test() {
+    let close =
+        |Point(x, y): Point, Point(u, v): Point| (x - u).abs() < 1e-12 && (y - v).abs() < 1e-12;
+    assert!(close(Point(0.0, 0.0).cos(), Point(1.0, 0.0)));
+    assert!(close(Point(0.0, 0.0).sin(), Point(0.0, 0.0)));
+    // along the imaginary axis they turn hyperbolic: cos(iy) = cosh y, sin(iy) = i sinh y
+    assert!(close(Point(0.0, 1.0).cos(), Point(1f64.cosh(), 0.0)));
+    assert!(close(Point(0.0, 1.0).sin(), Point(0.0, 1f64.sinh())));
+    // cos^2 + sin^2 = 1 off the real line too
+    let z = Point(0.7, -0.4);
+    assert!(close(
+        z.cos() * z.cos() + z.sin() * z.sin(),
+        Point(1.0, 0.0)
+    ));
+}
+
+#[test]
+fn powi_test() {
+    let z = Point(0.6, -1.3);
+    let Point(x, y) = z.powi(2);
+    let Point(u, v) = z * z;
+    assert_eq!((x, y), (u, v));
+    for n in -4..=7 {
+        let (Point(x, y), Point(u, v)) = (z.powi(n), z.powf(f64::from(n)));
+        assert!((x - u).abs() < 1e-9 && (y - v).abs() < 1e-9, "{}", n);
+    }
+    let Point(x, y) = z.powi(0);
+    assert_eq!((x, y), (1.0, 0.0));
+    let Point(x, y) = Point(0.0, 0.0).powi(-2);
+    assert_eq!((x, y), (0.0, 0.0));
+}
+
+#[test]
+fn div_test() {
+    let Point(x, y) = Point(-5.0, 10.0) / Point(1.0, 2.0);
+    assert!((x - 3.0).abs() < 1e-12 && (y - 4.0).abs() < 1e-12);
+}
+
+#[test]
+fn magnitude_test() {
+    assert_eq!(Point(3.0, -4.0).magnitude_squared(), 25.0);
+    assert_eq!(Point(3.0, -4.0).magnitude(), 5.0);
+    assert_eq!(Point(0.0, 0.0).magnitude(), 0.0);
+    // squaring 1e200 would overflow, but the magnitude itself fits
+    assert_eq!(Point(1e200, 0.0).magnitude(), 1e200);
+}
+
+#[test]
+fn rotated_test() {
+    use std::f64::consts::FRAC_PI_2;
+    let close =
+        |Point(x, y): Point, Point(u, v): Point| (x - u).abs() < 1e-12 && (y - v).abs() < 1e-12;
+    let center = Point(1.0, 1.0);
+    // a quarter turn anticlockwise takes the point right of the centre to the one above it
+    assert!(close(
+        Point(2.0, 1.0).rotated_about(center, FRAC_PI_2),
+        Point(1.0, 2.0)
+    ));
+    assert!(close(
+        Point(2.0, 1.0).rotated_about(center, -FRAC_PI_2),
+        Point(1.0, 0.0)
+    ));
+    assert!(close(center.rotated_about(center, 1.0), center));
+
+    let grid = Grid::new(5, 3, Point(-2.0, -1.0), Point(2.0, 1.0));
+    // no rotation keeps every point bit for bit
+    let bits = |grid: &Grid<Point>| grid.par_map_ref(|p| (p.0.to_bits(), p.1.to_bits())).0;
+    let unturned = grid.par_map_ref(|&p| p).rotated_about(Point(0.0, 0.0), 0.0);
+    assert_eq!(bits(&unturned), bits(&grid));
+    // a half turn about the middle reverses the grid
+    let turned = grid
+        .par_map_ref(|&p| p)
+        .rotated_about(Point(0.0, 0.0), std::f64::consts::PI);
+    for (row, turned_row) in grid.0.iter().zip(turned.0.iter().rev()) {
+        for (&p, &q) in row.iter().zip(turned_row.iter().rev()) {
+            assert!(close(p, q));
+        }
+    }
+}
+
+#[test]
+fn ln_powf_test() {
+    let close =
+        |Point(x, y): Point, Point(u, v): Point| (x - u).abs() < 1e-9 && (y - v).abs() < 1e-9;
+    for &z in &[Point(0.5, 0.25), Point(-1.5, 2.0), Point(0.1, -3.0)] {
+        assert!(close(z.exp().ln(), z));
+        assert!(close(z.powf(2.0), Point::next(Point(0.0, 0.0), z)));
+    }
+    assert_eq!(Point(-1.0, -0.0).ln().1, std::f64::consts::PI);
+    assert!(close(Point(-4.0, 0.0).powf(0.5), Point(0.0, 2.0)));
+}
+
+#[test]
+fn finish_test() {
+    // holds on to what it's given until it's finished, and can fail to write it out then
+    struct Buffered {
+        pending: Vec<Vec<u8>>,
+        written: Vec<Vec<u8>>,
+        fail: bool,
+    }
+    impl Renderer for Buffered {
+        type Item = u8;
+
+        fn render(&mut self, grid: Grid<u8>) {
+            self.pending = grid.0;
+        }
+
+        fn finish(&mut self) -> io::Result<()> {
+            if self.fail {
+                return Err(io::Error::other("disk full"));
+            }
+            self.written = std::mem::take(&mut self.pending);
+            Ok(())
+        }
+    }
+    let buffered = |fail| Buffered {
+        pending: vec![],
+        written: vec![],
+        fail,
+    };
+
+    let mandelbrot = crate::mandelbrot::Mandelbrot::new(Point(0.0, 0.0));
+    let points = Grid::new(4, 3, Point(-2.0, -1.0), Point(1.0, 1.0));
+    let mut expected = vec![];
+    draw(&mandelbrot, &Digits, 10, &mut expected, &points).unwrap();
+    let mut renderer = buffered(false);
+    draw(&mandelbrot, &Digits, 10, &mut renderer, &points).unwrap();
+    assert_eq!(renderer.written, expected);
+    let mut renderer = buffered(true);
+    let error = draw_sequential(&mandelbrot, &Digits, 10, &mut renderer, &points).unwrap_err();
+    assert_eq!(error.to_string(), "disk full");
 }
@@ -0,0 +1,45 @@
+use crate::fractal::{DerivativeOrbit, Generator, Point};
+
+/// The Glynn fractal, the Julia set of `z^1.5 - 0.2`, which grows fern-like fronds.
+#[derive(Default)]
+pub struct Glynn;
+
+const EXPONENT: f64 = 1.5;
+const C: Point = Point(-0.2, 0.0);
+
+impl Glynn {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Generator for Glynn {
+    type Output = impl Iterator<Item = Point>;
+
+    fn generate(&self, p: Point) -> Self::Output {
+        (0u32..).scan(p, move |acc, _| {
+            *acc = acc.powf(EXPONENT) + C;
+            Some(*acc)
+        })
+    }
+
+    // z' = 1.5 z^0.5 z', from z' = 1 at the point itself
+    fn generate_with_derivative(&self, p: Point) -> Option<DerivativeOrbit<'_>> {
+        let orbit = (0u32..).scan((p, Point(1.0, 0.0)), |(z, dz), _| {
+            *dz = z.powf(EXPONENT - 1.0) * *dz * EXPONENT;
+            *z = z.powf(EXPONENT) + C;
+            Some((*z, *dz))
+        });
+        Some(Box::new(orbit))
+    }
+}
+
+#[test]
+fn glynn_test() {
+    // 4^1.5 = 8, and 0.25^1.5 = 0.125
+    let mut orbit = Glynn::new().generate(Point(4.0, 0.0));
+    let Point(x, y) = orbit.next().unwrap();
+    assert!((x - 7.8).abs() < 1e-9 && y.abs() < 1e-9);
+    let Point(x, _) = Glynn::new().generate(Point(0.25, 0.0)).next().unwrap();
+    assert!((x + 0.075).abs() < 1e-9);
+}
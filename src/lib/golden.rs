@@ -0,0 +1,72 @@
+//! Renders small fixed views and compares them byte for byte with the PPMs checked in under
+//! `src/lib/golden`, to catch changes to coloring, orientation or grid math that the numeric
+//! tests would miss. After a change that's meant to alter the images, rerun the tests with
+//! `UPDATE_GOLDEN=1` to write new ones, and look at them before committing.
+
+use crate::{
+    draw_sequential, export::PngRenderer, julia::Julia, mandelbrot::Mandelbrot, rgb::RGBPalette,
+    Generator, Grid, Palette, Point,
+};
+use std::{env, fs, path::Path};
+
+const WIDTH: usize = 64;
+const HEIGHT: usize = 48;
+
+fn check_golden<G: Generator>(name: &str, generator: &G, min: Point, max: Point) {
+    let palette = RGBPalette::new();
+    let mut renderer = PngRenderer::new();
+    let grid = Grid::new(WIDTH, HEIGHT, min, max);
+    draw_sequential(
+        generator,
+        &palette,
+        palette.get().count(),
+        &mut renderer,
+        &grid,
+    )
+    .unwrap();
+    let mut rendered = vec![];
+    renderer.write_ppm(&mut rendered).unwrap();
+
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("src/lib/golden")
+        .join(format!("{}.ppm", name));
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&path, &rendered).unwrap();
+        return;
+    }
+    let golden =
+        fs::read(&path).unwrap_or_else(|e| panic!("couldn't read {}: {}", path.display(), e));
+    assert_eq!(rendered.len(), golden.len(), "{} changed size", name);
+    if let Some(i) = rendered.iter().zip(&golden).position(|(a, b)| a != b) {
+        // skip the "P6\n64 48\n255\n" header to find the pixel
+        let header = golden.len() - WIDTH * HEIGHT * 3;
+        let pixel = i.saturating_sub(header) / 3;
+        panic!(
+            "{} differs from {} first at pixel ({}, {}) from the top left",
+            name,
+            path.display(),
+            pixel % WIDTH,
+            pixel / WIDTH
+        );
+    }
+}
+
+#[test]
+fn mandelbrot_golden_test() {
+    check_golden(
+        "mandelbrot",
+        &Mandelbrot::new(Point(0.0, 0.0)),
+        Point(-2.25, -1.5),
+        Point(0.75, 1.5),
+    );
+}
+
+#[test]
+fn julia_golden_test() {
+    check_golden(
+        "julia",
+        &Julia::new(Point(0.32, 0.043)),
+        Point(-1.5, -1.5),
+        Point(1.5, 1.5),
+    );
+}
@@ -1,4 +1,8 @@
-use crate::fractal::{Generator, Point};
+use crate::{
+    fractal::{DerivativeOrbit, Generator, Point},
+    mandelbrot::Mandelbrot,
+};
+use rand::Rng;
 
 pub struct Julia {
     z: Point,
@@ -20,4 +24,130 @@ impl Generator for Julia {
             Some(*acc)
         })
     }
+
+    // z' = 2 z z', from z' = 1 at the point itself
+    fn generate_with_derivative(&self, p: Point) -> Option<DerivativeOrbit<'_>> {
+        let c = self.z;
+        let orbit = (0u32..).scan((p, Point(1.0, 0.0)), move |(z, dz), _| {
+            *dz = *z * *dz * 2.0;
+            *z = Point::next(c, *z);
+            Some((*z, *dz))
+        });
+        Some(Box::new(orbit))
+    }
+}
+
+/// A well-known Julia constant and a window that frames its whole set.
+#[derive(Debug)]
+pub struct Preset {
+    pub name: &'static str,
+    pub c: Point,
+    pub min: Point,
+    pub max: Point,
+}
+
+// a preset framed by a window centred on the origin
+const fn centred(name: &'static str, c: Point, half_width: f64, half_height: f64) -> Preset {
+    Preset {
+        name,
+        c,
+        min: Point(-half_width, -half_height),
+        max: Point(half_width, half_height),
+    }
+}
+
+/// Julia sets famous enough to have names, each framed to the 3:2 shape of the window.
+pub const PRESETS: &[Preset] = &[
+    centred("rabbit", Point(-0.123, 0.745), 1.875, 1.25),
+    centred("basilica", Point(-1.0, 0.0), 1.875, 1.25),
+    centred("san-marco", Point(-0.75, 0.0), 1.8, 1.2),
+    centred("dendrite", Point(0.0, 1.0), 1.8, 1.2),
+    centred("siegel-disk", Point(-0.390_541, -0.586_788), 1.8, 1.2),
+    centred("airplane", Point(-1.755, 0.0), 2.25, 1.5),
+    centred("cauliflower", Point(0.25, 0.0), 1.875, 1.25),
+];
+
+/// Looks up a preset by name, e.g. `"rabbit"`.
+pub fn preset(name: &str) -> Result<&'static Preset, String> {
+    PRESETS
+        .iter()
+        .find(|preset| preset.name == name)
+        .ok_or_else(|| {
+            let names: Vec<_> = PRESETS.iter().map(|preset| preset.name).collect();
+            format!(
+                "unknown Julia preset {:?}, expected one of {}",
+                name,
+                names.join(", ")
+            )
+        })
+}
+
+// c values whose Mandelbrot orbits escape within this range lie just outside the set, where the
+// Julia sets are intricate dust instead of a solid blob or a few scattered points
+const MIN_ESCAPE: u32 = 20;
+const MAX_ESCAPE: u32 = 500;
+
+/// Picks a Julia constant near the boundary of the Mandelbrot set, trying random points until
+/// one escapes neither too fast nor too slow.
+pub fn random_c<R: Rng>(rng: &mut R) -> Point {
+    let mandelbrot = Mandelbrot::new(Point(0.0, 0.0));
+    loop {
+        let c = Point(rng.gen_range(-2.0, 0.5), rng.gen_range(-1.25, 1.25));
+        if let Some(escape) = mandelbrot.escape_count(c, MAX_ESCAPE) {
+            if escape >= MIN_ESCAPE {
+                return c;
+            }
+        }
+    }
+}
+
+#[test]
+fn random_c_test() {
+    use rand::{rngs::StdRng, SeedableRng};
+    let mut rng = StdRng::seed_from_u64(7);
+    let Point(x, y) = random_c(&mut rng);
+    assert!((-2.0..0.5).contains(&x) && (-1.25..1.25).contains(&y));
+    let Point(u, v) = random_c(&mut StdRng::seed_from_u64(7));
+    assert_eq!((x, y), (u, v));
+}
+
+#[test]
+fn julia_test() {
+    // with c = 0 the Julia set is the unit circle: points inside stay, points outside escape
+    let julia = Julia::new(Point(0.0, 0.0));
+    let escape = |p| julia.generate(p).take(50).position(|z| julia.escaped(z));
+    assert_eq!(escape(Point(0.5, 0.0)), None);
+    // 2 -> 4 -> 16, and 16 is the first step outside the escape radius
+    assert_eq!(escape(Point(2.0, 0.0)), Some(1));
+}
+
+#[test]
+fn julia_matches_mandelbrot_test() {
+    // both yield the first step onwards, so starting Julia at zero retraces Mandelbrot at c
+    let c = Point(-0.4, 0.6);
+    let julia = Julia::new(c).generate(Point(0.0, 0.0));
+    let mandelbrot = Mandelbrot::new(Point(0.0, 0.0)).generate(c);
+    for (Point(x, y), Point(u, v)) in julia.zip(mandelbrot).take(20) {
+        assert_eq!((x, y), (u, v));
+    }
+}
+
+#[test]
+fn preset_test() {
+    let rabbit = preset("rabbit").unwrap();
+    assert_eq!((rabbit.c.0, rabbit.c.1), (-0.123, 0.745));
+    assert_eq!(
+        preset("bunny").unwrap_err(),
+        "unknown Julia preset \"bunny\", expected one of rabbit, basilica, san-marco, \
+         dendrite, siegel-disk, airplane, cauliflower"
+    );
+    for preset in PRESETS {
+        let (width, height) = (preset.max.0 - preset.min.0, preset.max.1 - preset.min.1);
+        assert!((width / height - 1.5).abs() < 1e-12, "{}", preset.name);
+        // the origin is in every one of these sets, and the window's corners are outside
+        let julia = Julia::new(preset.c);
+        let stays = |p| !julia.generate(p).take(200).any(|z| julia.escaped(z));
+        assert!(stays(Point(0.0, 0.0)), "{}", preset.name);
+        assert!(!stays(preset.min) && !stays(preset.max), "{}", preset.name);
+    }
 }
@@ -0,0 +1,107 @@
+use crate::fractal::{Grid, Point};
+
+// iterations to let the orbit settle before measuring, and iterations measured
+const WARMUP: usize = 200;
+const ITERATIONS: usize = 400;
+
+/// Lyapunov fractals of the logistic map, where each point `(a, b)` drives the map's growth
+/// rate with a periodic sequence of `a`s and `b`s.
+///
+/// This doesn't escape like the other fractals, so instead of implementing `Generator` it
+/// samples the Lyapunov exponent directly: negative exponents are stable, positive ones chaotic.
+pub struct Lyapunov {
+    // true wherever the sequence has a `B`
+    sequence: Vec<bool>,
+}
+
+impl Lyapunov {
+    /// Builds the fractal for a sequence like `"AB"` or `"AABAB"`.
+    pub fn new(sequence: &str) -> Result<Self, String> {
+        let sequence = sequence
+            .chars()
+            .map(|c| match c.to_ascii_uppercase() {
+                'A' => Ok(false),
+                'B' => Ok(true),
+                _ => Err(format!("sequences may only contain A and B, found {:?}", c)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if sequence.is_empty() {
+            return Err("the sequence can't be empty".to_owned());
+        }
+        Ok(Self { sequence })
+    }
+
+    pub fn exponent(&self, Point(a, b): Point) -> f64 {
+        let mut rates = self
+            .sequence
+            .iter()
+            .map(|&is_b| if is_b { b } else { a })
+            .cycle();
+        let mut x = 0.5;
+        for r in rates.by_ref().take(WARMUP) {
+            x = r * x * (1.0 - x);
+        }
+
+        let mut sum = 0.0;
+        for r in rates.take(ITERATIONS) {
+            x = r * x * (1.0 - x);
+            sum += (r * (1.0 - 2.0 * x)).abs().ln();
+        }
+        sum / ITERATIONS as f64
+    }
+
+    pub fn sample(&self, grid: &Grid<Point>) -> Grid<f64> {
+        grid.par_map_ref(|p| self.exponent(*p))
+    }
+}
+
+/// Maps exponents onto a diverging ramp: the most negative exponent in the grid gets the first
+/// item, zero gets the middle item, and the most positive exponent gets the last item.
+pub fn colorize<C: Copy>(exponents: &Grid<f64>, diverging: &[C]) -> Grid<C> {
+    let finite = || exponents.0.iter().flatten().filter(|e| e.is_finite());
+    let min = finite().cloned().fold(0.0, f64::min);
+    let max = finite().cloned().fold(0.0, f64::max);
+    let middle = (diverging.len() - 1) as f64 / 2.0;
+
+    let pick = |e: f64| {
+        let t = if e < 0.0 {
+            if e.is_finite() {
+                e / -min
+            } else {
+                -1.0
+            }
+        } else if max > 0.0 {
+            e.min(max) / max
+        } else {
+            0.0
+        };
+        diverging[(middle + t * middle).round() as usize]
+    };
+
+    Grid(
+        exponents
+            .0
+            .iter()
+            .map(|row| row.iter().map(|&e| pick(e)).collect())
+            .collect(),
+    )
+}
+
+#[test]
+fn lyapunov_test() {
+    // r = 2 is a superstable point of the logistic map, while r = 4 is fully chaotic
+    let lyapunov = Lyapunov::new("AB").unwrap();
+    assert!(lyapunov.exponent(Point(2.0, 2.0)) < 0.0);
+    assert!(lyapunov.exponent(Point(4.0, 4.0)) > 0.0);
+    assert!(Lyapunov::new("ABC").is_err());
+    assert!(Lyapunov::new("").is_err());
+}
+
+#[test]
+fn colorize_test() {
+    let exponents = Grid(vec![vec![-2.0, -1.0, 0.0, 0.5, 1.0]]);
+    assert_eq!(
+        colorize(&exponents, &[0, 1, 2, 3, 4]).0,
+        vec![vec![0, 1, 2, 3, 4]]
+    );
+}
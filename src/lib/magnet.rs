@@ -0,0 +1,81 @@
+use crate::fractal::{Generator, Point};
+
+// orbits further out than this are on their way to infinity
+const BAILOUT: f64 = 100.0;
+// orbits this close to 1 have settled on it
+const CONVERGED: f64 = 1e-6;
+
+/// The Magnet Type I fractal, from a model of magnetic phase transitions:
+/// `z -> ((z^2 + c - 1) / (2z + c - 2))^2`, starting from 0.
+///
+/// Unlike the escape-time fractals, much of the inside of this set is made up of the points
+/// whose orbits settle on the fixed point at 1 rather than just staying bounded. Those orbits
+/// stay close however many iterations are followed, so they're colored as the interior, and
+/// once one has settled it stops moving instead of being iterated any further.
+pub struct MagnetI;
+
+impl MagnetI {
+    pub fn new() -> Self {
+        Self
+    }
+
+    fn next(c: Point, z: Point) -> Point {
+        let one = Point(1.0, 0.0);
+        let ratio = (z * z + c - one) / (z * 2.0 + c - one * 2.0);
+        ratio * ratio
+    }
+
+    /// Whether an orbit at `z` has settled on the fixed point at 1.
+    pub fn converged(Point(x, y): Point) -> bool {
+        let dx = x - 1.0;
+        dx * dx + y * y < CONVERGED * CONVERGED
+    }
+}
+
+impl Default for MagnetI {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Generator for MagnetI {
+    type Output = impl Iterator<Item = Point>;
+
+    fn generate(&self, c: Point) -> Self::Output {
+        (0u32..).scan(Point(0.0, 0.0), move |z, _| {
+            if !MagnetI::converged(*z) {
+                *z = MagnetI::next(c, *z);
+            }
+            Some(*z)
+        })
+    }
+
+    fn escaped(&self, z: Point) -> bool {
+        z.magnitude_squared() > BAILOUT * BAILOUT
+    }
+
+    fn bailout(&self) -> f64 {
+        BAILOUT
+    }
+}
+
+#[test]
+fn magnet_test() {
+    let magnet = MagnetI::new();
+    // at c = 2 the first step is ((0 + 1) / 0)^2, which overflows straight away
+    let Point(x, _) = magnet.generate(Point(2.0, 0.0)).next().unwrap();
+    assert!(!x.is_finite());
+    // at c = -1 the orbit goes 4/9, 0.729, 0.907 and on towards 1, then stops there
+    let orbit: Vec<_> = magnet.generate(Point(-1.0, 0.0)).take(20).collect();
+    assert!((orbit[0].0 - 4.0 / 9.0).abs() < 1e-12);
+    let settled = orbit.iter().position(|&z| MagnetI::converged(z)).unwrap();
+    assert!(orbit[settled..].iter().all(|&z| z.0 == orbit[settled].0));
+    // and near c = 3 + 0.7i it escapes after a few steps
+    assert!(magnet
+        .generate(Point(3.0, 0.7))
+        .take(50)
+        .any(|z| magnet.escaped(z)));
+    // smooth coloring measures escapes against the same radius
+    let r = magnet.bailout();
+    assert!(magnet.escaped(Point(r * 1.01, 0.0)) && !magnet.escaped(Point(r * 0.99, 0.0)));
+}
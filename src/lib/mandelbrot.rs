@@ -1,4 +1,4 @@
-use crate::fractal::{Generator, Point};
+use crate::fractal::{escape_point, DerivativeOrbit, EscapeResult, Generator, Point};
 
 pub struct Mandelbrot {
     z: Point,
@@ -8,6 +8,37 @@ impl Mandelbrot {
     pub fn new(z: Point) -> Self {
         Self { z }
     }
+
+    /// The index of the first step where the orbit of `p` escapes, or `None` if it's still
+    /// close after `max_iter` steps. Orbits starting from zero skip the iterating for points
+    /// in the main cardioid or the period 2 bulb, which never escape.
+    pub fn escape_count(&self, p: Point, max_iter: u32) -> Option<u32> {
+        self.escape(p, max_iter).count.map(|escape| escape as u32)
+    }
+
+    /// Like `escape_count`, but keeps the point the orbit escaped to as well, which smooth and
+    /// angle-based coloring need. Points skipped by the cardioid and bulb check stay at `p`.
+    pub fn escape(&self, p: Point, max_iter: u32) -> EscapeResult {
+        if self.z.0 == 0.0 && self.z.1 == 0.0 && Mandelbrot::in_cardioid_or_bulb(p) {
+            return EscapeResult {
+                count: None,
+                last: p,
+            };
+        }
+        escape_point(self, p, max_iter as usize)
+    }
+
+    /// Whether `p` is in the set. This is only an approximation: points whose orbits escape
+    /// after more than `max_iter` steps are counted as inside, so raising `max_iter` can only
+    /// shrink the set.
+    pub fn contains(&self, p: Point, max_iter: u32) -> bool {
+        self.escape_count(p, max_iter).is_none()
+    }
+
+    fn in_cardioid_or_bulb(Point(x, y): Point) -> bool {
+        let q = (x - 0.25) * (x - 0.25) + y * y;
+        q * (q + x - 0.25) <= y * y / 4.0 || (x + 1.0) * (x + 1.0) + y * y <= 1.0 / 16.0
+    }
 }
 
 impl Generator for Mandelbrot {
@@ -19,6 +50,16 @@ impl Generator for Mandelbrot {
             Some(*acc)
         })
     }
+
+    // z' = 2 z z' + 1, from z' = 0 at the start
+    fn generate_with_derivative(&self, p: Point) -> Option<DerivativeOrbit<'_>> {
+        let orbit = (0u32..).scan((self.z, Point(0.0, 0.0)), move |(z, dz), _| {
+            *dz = *z * *dz * 2.0 + Point(1.0, 0.0);
+            *z = Point::next(p, *z);
+            Some((*z, *dz))
+        });
+        Some(Box::new(orbit))
+    }
 }
 
 #[test]
@@ -33,3 +74,49 @@ fn mandelbrot_test() {
         assert!(val < 0.001, format!("Expected {} to match {}", left, right));
     }
 }
+
+#[test]
+fn contains_test() {
+    let mandelbrot = Mandelbrot::new(Point(0.0, 0.0));
+    // the cardioid, the bulb, and a point in neither that iterates its way to a 3-cycle
+    for &p in &[Point(0.0, 0.0), Point(-1.0, 0.1), Point(-0.12, 0.75)] {
+        assert!(mandelbrot.contains(p, 200), "{:?}", p);
+    }
+    assert!(!mandelbrot.contains(Point(0.5, 0.0), 200));
+    assert!(!mandelbrot.contains(Point(-2.1, 0.0), 200));
+    // the fast check agrees with iterating from a nonzero start that lands back on the same orbits
+    let shifted = Mandelbrot::new(Point(1e-300, 0.0));
+    assert_eq!(shifted.escape_count(Point(0.2, 0.1), 200), None);
+    assert_eq!(
+        mandelbrot.escape_count(Point(0.5, 0.0), 200),
+        shifted.escape_count(Point(0.5, 0.0), 200)
+    );
+}
+
+#[test]
+fn escape_test() {
+    use crate::fractal::ESCAPE_RADIUS;
+    let mandelbrot = Mandelbrot::new(Point(0.0, 0.0));
+    for &p in &[
+        Point(0.5, 0.0),
+        Point(-2.1, 0.0),
+        Point(0.3, 0.6),
+        Point(-0.75, 0.1),
+    ] {
+        let escape = mandelbrot.escape(p, 200);
+        let count = escape.count.expect("all of these escape");
+        assert!(
+            escape.last.magnitude() >= ESCAPE_RADIUS,
+            "{:?} escaped to {:?}",
+            p,
+            escape.last
+        );
+        // and it's the first such point, as every step before it was still close
+        for z in mandelbrot.generate(p).take(count) {
+            assert!(z.magnitude() < ESCAPE_RADIUS);
+        }
+        assert_eq!(mandelbrot.escape_count(p, 200), Some(count as u32));
+    }
+    let inside = mandelbrot.escape(Point(-0.12, 0.75), 200);
+    assert_eq!(inside.count, None);
+}
@@ -1,9 +1,34 @@
 #![feature(type_alias_impl_trait)]
 
+pub mod burning_ship;
+pub mod celtic;
 pub mod char;
+pub mod collatz;
+pub mod colorize;
+#[cfg(feature = "deep-zoom")]
+pub mod deep;
+pub mod exponential;
+pub mod export;
 mod fractal;
+pub mod glynn;
+#[cfg(test)]
+mod golden;
 pub mod julia;
+pub mod lyapunov;
+pub mod magnet;
 pub mod mandelbrot;
+pub mod multibrot;
+pub mod nova;
+pub mod perpendicular;
+pub mod power;
+#[cfg(feature = "deep-zoom")]
+pub mod precision;
+pub mod quaternion;
+pub mod registry;
 pub mod rgb;
+pub mod spider;
+pub mod stats;
+pub mod trap;
+pub mod view;
 
 pub use fractal::*;
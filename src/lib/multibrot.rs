@@ -0,0 +1,47 @@
+use crate::{
+    fractal::{DerivativeOrbit, Generator, Point},
+    power::PowerMandelbrot,
+};
+
+/// The Mandelbrot set generalised to `z^e + c` for any real exponent `e`. Whole exponents give
+/// `e - 1` fold symmetry; fractional ones tear the set along the branch cut of `z^e`. This is
+/// `PowerMandelbrot` under the name the `multibrot` subcommand knows it by.
+pub struct Multibrot(PowerMandelbrot);
+
+impl Multibrot {
+    pub fn new(exponent: f64) -> Self {
+        Self(PowerMandelbrot::new(exponent))
+    }
+}
+
+impl Generator for Multibrot {
+    type Output = <PowerMandelbrot as Generator>::Output;
+
+    fn generate(&self, p: Point) -> Self::Output {
+        self.0.generate(p)
+    }
+
+    fn escaped(&self, z: Point) -> bool {
+        self.0.escaped(z)
+    }
+
+    fn bailout(&self) -> f64 {
+        self.0.bailout()
+    }
+
+    fn generate_with_derivative(&self, p: Point) -> Option<DerivativeOrbit<'_>> {
+        self.0.generate_with_derivative(p)
+    }
+}
+
+#[test]
+fn multibrot_test() {
+    use crate::mandelbrot::Mandelbrot;
+    // squaring is the Mandelbrot set again
+    let c = Point(-0.5, 0.5);
+    let multibrot = Multibrot::new(2.0).generate(c);
+    let mandelbrot = Mandelbrot::new(Point(0.0, 0.0)).generate(c);
+    for (Point(x, y), Point(u, v)) in multibrot.zip(mandelbrot).take(10) {
+        assert!((x - u).abs() < 1e-9 && (y - v).abs() < 1e-9);
+    }
+}
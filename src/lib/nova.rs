@@ -0,0 +1,52 @@
+use crate::fractal::{Generator, Point};
+
+// steps shorter than this mean the orbit has settled on a root
+const CONVERGED: f64 = 1e-6;
+
+/// The Nova fractal, Newton's method for `z^3 - 1` with relaxation `r` and an added constant:
+/// `z -> z - r (z^3 - 1) / 3z^2 + c`, starting from each point of the plane.
+///
+/// Most orbits settle down rather than escape, so an orbit ends once it has converged, and the
+/// escape-time coloring then shows how long that took. Orbits that neither converge nor escape
+/// are colored as the interior.
+pub struct Nova {
+    relaxation: f64,
+    c: Point,
+}
+
+impl Nova {
+    pub fn new(relaxation: f64, c: Point) -> Self {
+        Self { relaxation, c }
+    }
+
+    fn next(&self, z: Point) -> Point {
+        let z2 = z * z;
+        z - (z2 * z - Point(1.0, 0.0)) / (z2 * 3.0) * self.relaxation + self.c
+    }
+}
+
+impl Generator for Nova {
+    type Output = impl Iterator<Item = Point>;
+
+    fn generate(&self, p: Point) -> Self::Output {
+        let nova = Nova::new(self.relaxation, self.c);
+        (0u32..)
+            .scan(p, move |acc, _| {
+                let next = nova.next(*acc);
+                let Point(dx, dy) = next - *acc;
+                *acc = next;
+                Some((next, dx * dx + dy * dy < CONVERGED * CONVERGED))
+            })
+            .take_while(|&(_, converged)| !converged)
+            .map(|(z, _)| z)
+    }
+}
+
+#[test]
+fn nova_test() {
+    // without a constant this is plain Newton's method, which finds the root at 1 from 2
+    let nova = Nova::new(1.0, Point(0.0, 0.0));
+    let Point(x, y) = nova.generate(Point(2.0, 0.0)).last().unwrap();
+    assert!((x - 1.0).abs() < 1e-6 && y.abs() < 1e-6);
+    assert!(nova.generate(Point(2.0, 0.0)).count() < 10);
+}
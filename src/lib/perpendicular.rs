@@ -0,0 +1,33 @@
+use crate::fractal::{Generator, Point};
+
+/// The "perpendicular" Mandelbrot, which squares `|Re z| - i Im z` instead of `z`.
+#[derive(Default)]
+pub struct PerpendicularMandelbrot;
+
+impl PerpendicularMandelbrot {
+    pub fn new() -> Self {
+        Self
+    }
+
+    fn next(Point(u, v): Point, Point(x, y): Point) -> Point {
+        Point(x * x - y * y + u, -2.0 * x.abs() * y + v)
+    }
+}
+
+impl Generator for PerpendicularMandelbrot {
+    type Output = impl Iterator<Item = Point>;
+
+    fn generate(&self, p: Point) -> Self::Output {
+        (0u32..).scan(Point(0.0, 0.0), move |acc, _| {
+            *acc = PerpendicularMandelbrot::next(p, *acc);
+            Some(*acc)
+        })
+    }
+}
+
+#[test]
+fn perpendicular_test() {
+    // (1 - 2i)^2 = -3 - 4i, where the plain Mandelbrot step would give -3 + 4i
+    let Point(x, y) = PerpendicularMandelbrot::next(Point(0.0, 0.0), Point(1.0, 2.0));
+    assert!((x + 3.0).abs() < 1e-9 && (y + 4.0).abs() < 1e-9);
+}
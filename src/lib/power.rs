@@ -0,0 +1,95 @@
+use crate::fractal::{DerivativeOrbit, Generator, Point};
+
+/// How a `PowerMandelbrot` raises each step to its power.
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Power {
+    // by repeated multiplication, exactly as squaring by hand would
+    Whole(i32),
+    // through `powf`, on the principal branch
+    Real(f64),
+}
+
+/// The Mandelbrot set raised to any real power, `z -> z^n + c` starting from 0. Whole powers
+/// are computed by multiplying, which is faster than going through logarithms and matches the
+/// classic set exactly for `n = 2`; other powers use `Point::powf`.
+pub struct PowerMandelbrot {
+    power: Power,
+}
+
+impl PowerMandelbrot {
+    pub fn new(power: f64) -> Self {
+        let whole = power.fract() == 0.0 && power.abs() <= f64::from(i32::MAX);
+        Self {
+            power: if whole {
+                Power::Whole(power as i32)
+            } else {
+                Power::Real(power)
+            },
+        }
+    }
+}
+
+impl Generator for PowerMandelbrot {
+    type Output = impl Iterator<Item = Point>;
+
+    fn generate(&self, p: Point) -> Self::Output {
+        let power = self.power;
+        (0u32..).scan(Point(0.0, 0.0), move |acc, _| {
+            let raised = match power {
+                Power::Whole(n) => acc.powi(n),
+                Power::Real(e) => acc.powf(e),
+            };
+            *acc = raised + p;
+            Some(*acc)
+        })
+    }
+
+    // z' = n z^(n - 1) z' + 1, from z' = 0 at the start
+    fn generate_with_derivative(&self, p: Point) -> Option<DerivativeOrbit<'_>> {
+        let power = self.power;
+        let orbit = (0u32..).scan((Point(0.0, 0.0), Point(0.0, 0.0)), move |(z, dz), _| {
+            let (lowered, raised, n) = match power {
+                Power::Whole(n) => (z.powi(n - 1), z.powi(n), f64::from(n)),
+                Power::Real(e) => (z.powf(e - 1.0), z.powf(e), e),
+            };
+            *dz = lowered * *dz * n + Point(1.0, 0.0);
+            *z = raised + p;
+            Some((*z, *dz))
+        });
+        Some(Box::new(orbit))
+    }
+}
+
+#[test]
+fn power_mandelbrot_test() {
+    use crate::mandelbrot::Mandelbrot;
+    assert_eq!(PowerMandelbrot::new(2.0).power, Power::Whole(2));
+    assert_eq!(PowerMandelbrot::new(-3.0).power, Power::Whole(-3));
+    assert_eq!(PowerMandelbrot::new(1.5).power, Power::Real(1.5));
+    assert_eq!(PowerMandelbrot::new(1e10).power, Power::Real(1e10));
+
+    // squaring is the Mandelbrot set again, to the last bit
+    for &c in &[Point(-0.5, 0.5), Point(0.3, -0.6), Point(-1.75, 0.01)] {
+        let power = PowerMandelbrot::new(2.0).generate(c);
+        let mandelbrot = Mandelbrot::new(Point(0.0, 0.0)).generate(c);
+        for (Point(x, y), Point(u, v)) in power.zip(mandelbrot).take(50) {
+            assert_eq!((x.to_bits(), y.to_bits()), (u.to_bits(), v.to_bits()));
+        }
+        // and so are the derivatives alongside
+        let power = PowerMandelbrot::new(2.0);
+        let mandelbrot = Mandelbrot::new(Point(0.0, 0.0));
+        let orbits = power.generate_with_derivative(c).unwrap();
+        let expected = mandelbrot.generate_with_derivative(c).unwrap();
+        for ((_, Point(x, y)), (_, Point(u, v))) in orbits.zip(expected).take(50) {
+            assert_eq!((x.to_bits(), y.to_bits()), (u.to_bits(), v.to_bits()));
+        }
+    }
+    // and fractional powers go through powf
+    let c = Point(-0.4, 0.2);
+    let power = PowerMandelbrot::new(2.5).generate(c);
+    let mut z = Point(0.0, 0.0);
+    for Point(x, y) in power.take(10) {
+        z = z.powf(2.5) + c;
+        assert_eq!((x, y), (z.0, z.1));
+    }
+}
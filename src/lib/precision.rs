@@ -0,0 +1,195 @@
+use std::{
+    fmt,
+    ops::{Add, Mul, Neg, Sub},
+    str::FromStr,
+};
+
+/// The arithmetic an orbit needs, so the same iteration can run in `f64` or in something
+/// more precise.
+pub trait Real:
+    Copy + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Neg<Output = Self>
+{
+    fn from_f64(x: f64) -> Self;
+    fn to_f64(self) -> f64;
+}
+
+impl Real for f64 {
+    fn from_f64(x: f64) -> Self {
+        x
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+}
+
+/// A number kept as the unevaluated sum of two `f64`s, the second holding what the first
+/// couldn't, for about 32 significant digits instead of 16.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DoubleDouble {
+    hi: f64,
+    lo: f64,
+}
+
+// a + b exactly, as the rounded sum and the error, when |a| >= |b|
+fn quick_two_sum(a: f64, b: f64) -> DoubleDouble {
+    let hi = a + b;
+    DoubleDouble {
+        hi,
+        lo: b - (hi - a),
+    }
+}
+
+// a + b exactly, as the rounded sum and the error
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let sum = a + b;
+    let b_part = sum - a;
+    (sum, (a - (sum - b_part)) + (b - b_part))
+}
+
+impl DoubleDouble {
+    /// Divides by a plain `f64`, which is all parsing needs.
+    pub fn div_f64(self, divisor: f64) -> DoubleDouble {
+        let first = self.hi / divisor;
+        let product = first * divisor;
+        let product_error = first.mul_add(divisor, -product);
+        let (rest, rest_error) = two_sum(self.hi, -product);
+        let rest = rest + (rest_error - product_error + self.lo);
+        quick_two_sum(first, rest / divisor)
+    }
+}
+
+impl Real for DoubleDouble {
+    fn from_f64(hi: f64) -> Self {
+        DoubleDouble { hi, lo: 0.0 }
+    }
+
+    fn to_f64(self) -> f64 {
+        self.hi + self.lo
+    }
+}
+
+impl Add for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn add(self, other: DoubleDouble) -> DoubleDouble {
+        let (hi, lo) = two_sum(self.hi, other.hi);
+        quick_two_sum(hi, lo + self.lo + other.lo)
+    }
+}
+
+impl Neg for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn neg(self) -> DoubleDouble {
+        DoubleDouble {
+            hi: -self.hi,
+            lo: -self.lo,
+        }
+    }
+}
+
+impl Sub for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn sub(self, other: DoubleDouble) -> DoubleDouble {
+        self + -other
+    }
+}
+
+impl Mul for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn mul(self, other: DoubleDouble) -> DoubleDouble {
+        let hi = self.hi * other.hi;
+        let lo = self.hi.mul_add(other.hi, -hi);
+        quick_two_sum(hi, lo + self.hi * other.lo + self.lo * other.hi)
+    }
+}
+
+/// Reads a plain decimal like `-0.743643887037158704752191506114774`, keeping every digit
+/// that fits instead of rounding to the nearest `f64` first.
+impl FromStr for DoubleDouble {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<DoubleDouble, String> {
+        let invalid = || format!("{:?} isn't a decimal number", s);
+        let (negative, digits) = match s.trim().strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, s.trim().trim_start_matches('+')),
+        };
+        let (whole, fraction) = match digits.split_once('.') {
+            Some((whole, fraction)) => (whole, fraction),
+            None => (digits, ""),
+        };
+        if whole.is_empty() && fraction.is_empty() {
+            return Err(invalid());
+        }
+        let digit = |c: char| c.to_digit(10).map(f64::from).ok_or_else(invalid);
+        let mut value = DoubleDouble::from_f64(0.0);
+        for c in whole.chars().chain(fraction.chars()) {
+            value = value * DoubleDouble::from_f64(10.0) + DoubleDouble::from_f64(digit(c)?);
+        }
+        for _ in 0..fraction.len() {
+            value = value.div_f64(10.0);
+        }
+        Ok(if negative { -value } else { value })
+    }
+}
+
+// digits after the point to write out, a couple more than a double-double holds
+const DISPLAY_DIGITS: usize = 34;
+
+/// Writes the number as a plain decimal that `from_str` reads back to the same value.
+impl fmt::Display for DoubleDouble {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let negative = self.to_f64() < 0.0;
+        let value = if negative { -*self } else { *self };
+        let mut whole = value.to_f64().trunc();
+        let mut fraction = value - DoubleDouble::from_f64(whole);
+        if fraction.to_f64() < 0.0 {
+            whole -= 1.0;
+            fraction = fraction + DoubleDouble::from_f64(1.0);
+        }
+        let mut digits = String::new();
+        for _ in 0..DISPLAY_DIGITS {
+            fraction = fraction * DoubleDouble::from_f64(10.0);
+            let digit = fraction.to_f64().floor().clamp(0.0, 9.0);
+            fraction = fraction - DoubleDouble::from_f64(digit);
+            digits.push(char::from(b'0' + digit as u8));
+        }
+        let digits = digits.trim_end_matches('0');
+        write!(f, "{}{}", if negative { "-" } else { "" }, whole)?;
+        if !digits.is_empty() {
+            write!(f, ".{}", digits)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn double_double_test() {
+    let one = DoubleDouble::from_f64(1.0);
+    let tiny = DoubleDouble::from_f64(1e-20);
+    // an f64 would lose the 1e-20 entirely
+    assert_eq!(((one + tiny) - one).to_f64(), 1e-20);
+    let third = one.div_f64(3.0);
+    let error = (third * DoubleDouble::from_f64(3.0) - one).to_f64();
+    assert!(error.abs() < 1e-30, "{}", error);
+
+    let parsed: DoubleDouble = "-0.1234567890123456789012345".parse().unwrap();
+    assert_eq!(parsed.to_f64(), -0.123_456_789_012_345_68);
+    let rest = parsed + "0.1234567890123456".parse().unwrap();
+    assert!((rest.to_f64() + 7.89012345e-17).abs() < 1e-28, "{:?}", rest);
+    let text = "-0.743643887037158704752191506114774";
+    let center: DoubleDouble = text.parse().unwrap();
+    let written = center.to_string();
+    // the last couple of digits are past what a double-double holds
+    assert!(written.starts_with(&text[..34]), "{}", written);
+    assert_eq!(written.parse::<DoubleDouble>().unwrap(), center);
+    assert_eq!(DoubleDouble::from_f64(12.5).to_string(), "12.5");
+    assert_eq!(DoubleDouble::from_f64(0.0).to_string(), "0");
+    for invalid in &["", "-", "1.2.3", "1e5", "abc"] {
+        assert!(invalid.parse::<DoubleDouble>().is_err(), "{}", invalid);
+    }
+}
@@ -0,0 +1,84 @@
+use crate::fractal::{Generator, Point};
+use std::ops::{Add, Mul};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Quaternion(f64, f64, f64, f64);
+
+impl Add for Quaternion {
+    type Output = Quaternion;
+
+    fn add(self, Quaternion(a, b, c, d): Quaternion) -> Quaternion {
+        Quaternion(self.0 + a, self.1 + b, self.2 + c, self.3 + d)
+    }
+}
+
+impl Mul for Quaternion {
+    type Output = Quaternion;
+
+    fn mul(self, Quaternion(e, f, g, h): Quaternion) -> Quaternion {
+        let Quaternion(a, b, c, d) = self;
+        Quaternion(
+            a * e - b * f - c * g - d * h,
+            a * f + b * e + c * h - d * g,
+            a * g - b * h + c * e + d * f,
+            a * h + b * g - c * f + d * e,
+        )
+    }
+}
+
+/// A 2D slice through the quaternion Julia set of `q -> q^2 + c`. Each point of the plane
+/// sets the first two components of `q`, and `slice` fixes the other two.
+///
+/// The orbit comes out as the real part of `q` and the length of the rest, which has the same
+/// distance from zero as `q` itself, so the usual escape test still applies.
+pub struct QuaternionJulia {
+    c: Quaternion,
+    slice: (f64, f64),
+}
+
+impl QuaternionJulia {
+    pub fn new(c: [f64; 4], slice: (f64, f64)) -> Self {
+        Self {
+            c: Quaternion(c[0], c[1], c[2], c[3]),
+            slice,
+        }
+    }
+}
+
+impl Generator for QuaternionJulia {
+    type Output = impl Iterator<Item = Point>;
+
+    fn generate(&self, Point(x, y): Point) -> Self::Output {
+        let c = self.c;
+        let start = Quaternion(x, y, self.slice.0, self.slice.1);
+        (0u32..).scan(start, move |q, _| {
+            *q = *q * *q + c;
+            let Quaternion(a, b, c, d) = *q;
+            Some(Point(a, (b * b + c * c + d * d).sqrt()))
+        })
+    }
+}
+
+#[test]
+fn quaternion_test() {
+    let (i, j, k) = (
+        Quaternion(0.0, 1.0, 0.0, 0.0),
+        Quaternion(0.0, 0.0, 1.0, 0.0),
+        Quaternion(0.0, 0.0, 0.0, 1.0),
+    );
+    assert_eq!(i * j, k);
+    assert_eq!(j * i, Quaternion(0.0, 0.0, 0.0, -1.0));
+    assert_eq!(k * k, Quaternion(-1.0, 0.0, 0.0, 0.0));
+}
+
+#[test]
+fn quaternion_julia_test() {
+    // with the slice at zero and c complex, this is an ordinary Julia set
+    use crate::julia::Julia;
+    let quaternion = QuaternionJulia::new([-0.4, 0.6, 0.0, 0.0], (0.0, 0.0));
+    let julia = Julia::new(Point(-0.4, 0.6));
+    let p = Point(0.3, -0.2);
+    for (Point(a, v), Point(x, y)) in quaternion.generate(p).zip(julia.generate(p)).take(10) {
+        assert!((a - x).abs() < 1e-9 && (v - y.abs()).abs() < 1e-9);
+    }
+}
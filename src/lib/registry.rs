@@ -0,0 +1,208 @@
+use crate::{
+    boxed, burning_ship::BurningShip, celtic::Celtic, collatz::Collatz, exponential::ExpFractal,
+    glynn::Glynn, julia::Julia, magnet::MagnetI, mandelbrot::Mandelbrot, multibrot::Multibrot,
+    nova::Nova, perpendicular::PerpendicularMandelbrot, spider::Spider, BoxedGenerator, Point,
+};
+
+/// A fractal that can be picked by name, with the view it starts at and a way to make its
+/// generator from whatever parameters were given.
+#[derive(Debug)]
+pub struct Fractal {
+    pub name: &'static str,
+    /// What the window showing it is called.
+    pub title: &'static str,
+    pub min: Point,
+    pub max: Point,
+    pub generator: fn(&Parameters) -> BoxedGenerator,
+    /// The power z is raised to each step, which smooth coloring needs to make the bands meet.
+    /// Ones that don't raise z to a power are colored as if it were squared.
+    pub power: f64,
+}
+
+/// What a generator can be made with besides its defaults. Each fractal only reads the ones it
+/// takes, using its usual value for any left out.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Parameters {
+    /// The constant Julia and Nova add each step.
+    pub c: Option<Point>,
+    /// The power the Multibrot set raises z to.
+    pub power: Option<f64>,
+    /// How far along each Newton step Nova moves.
+    pub relaxation: Option<f64>,
+}
+
+/// The constant the Julia set is drawn with unless another is given.
+pub const JULIA_C: Point = Point(0.32, 0.043);
+
+/// The power the Multibrot set raises z to unless another is given.
+pub const MULTIBROT_POWER: f64 = 3.0;
+
+/// How far along each Newton step Nova moves unless told otherwise.
+pub const NOVA_RELAXATION: f64 = 1.0;
+
+/// The constant Nova adds after each step unless another is given.
+pub const NOVA_C: Point = Point(-0.3, 0.0);
+
+pub const MANDELBROT: Fractal = Fractal {
+    name: "mandelbrot",
+    title: "Mandelbrot",
+    min: Point(-2.25, -1.5),
+    max: Point(0.75, 1.5),
+    generator: |_| boxed(Mandelbrot::new(Point(0.0, 0.0))),
+    power: 2.0,
+};
+
+pub const JULIA: Fractal = Fractal {
+    name: "julia",
+    title: "Julia",
+    min: Point(-1.5, -1.5),
+    max: Point(1.5, 1.5),
+    generator: |params| boxed(Julia::new(params.c.unwrap_or(JULIA_C))),
+    power: 2.0,
+};
+
+pub const BURNING_SHIP: Fractal = Fractal {
+    name: "burning-ship",
+    title: "Burning Ship",
+    min: Point(-2.25, -1.75),
+    max: Point(1.25, 1.0),
+    generator: |_| boxed(BurningShip::new()),
+    power: 2.0,
+};
+
+pub const COLLATZ: Fractal = Fractal {
+    name: "collatz",
+    title: "Collatz",
+    min: Point(-2.5, -1.0),
+    max: Point(2.5, 1.0),
+    generator: |_| boxed(Collatz::new()),
+    power: 2.0,
+};
+
+pub const CELTIC: Fractal = Fractal {
+    name: "celtic",
+    title: "Celtic",
+    min: Point(-2.25, -1.5),
+    max: Point(0.75, 1.5),
+    generator: |_| boxed(Celtic::new()),
+    power: 2.0,
+};
+
+pub const MULTIBROT: Fractal = Fractal {
+    name: "multibrot",
+    title: "Multibrot",
+    min: Point(-1.875, -1.25),
+    max: Point(1.875, 1.25),
+    generator: |params| boxed(Multibrot::new(params.power.unwrap_or(MULTIBROT_POWER))),
+    power: MULTIBROT_POWER,
+};
+
+pub const PERPENDICULAR: Fractal = Fractal {
+    name: "perpendicular",
+    title: "Perpendicular Mandelbrot",
+    min: Point(-2.25, -1.5),
+    max: Point(1.5, 1.5),
+    generator: |_| boxed(PerpendicularMandelbrot::new()),
+    power: 2.0,
+};
+
+pub const NOVA: Fractal = Fractal {
+    name: "nova",
+    title: "Nova",
+    min: Point(-1.5, -1.0),
+    max: Point(1.5, 1.0),
+    generator: |params| {
+        let relaxation = params.relaxation.unwrap_or(NOVA_RELAXATION);
+        boxed(Nova::new(relaxation, params.c.unwrap_or(NOVA_C)))
+    },
+    power: 2.0,
+};
+
+pub const EXPONENTIAL: Fractal = Fractal {
+    name: "exponential",
+    title: "Exponential",
+    min: Point(-1.0, -1.5),
+    max: Point(3.0, 1.5),
+    generator: |_| boxed(ExpFractal::new()),
+    power: 2.0,
+};
+
+pub const MAGNET: Fractal = Fractal {
+    name: "magnet",
+    title: "Magnet",
+    min: Point(-2.5, -2.5),
+    max: Point(5.0, 2.5),
+    generator: |_| boxed(MagnetI::new()),
+    power: 2.0,
+};
+
+pub const SPIDER: Fractal = Fractal {
+    name: "spider",
+    title: "Spider",
+    min: Point(-3.0, -1.5),
+    max: Point(1.5, 1.5),
+    generator: |_| boxed(Spider::new()),
+    power: 2.0,
+};
+
+// the leftmost bulb, where the fronds are biggest
+pub const GLYNN: Fractal = Fractal {
+    name: "glynn",
+    title: "Glynn",
+    min: Point(-0.68, -0.105),
+    max: Point(-0.365, 0.105),
+    generator: |_| boxed(Glynn::new()),
+    power: 2.0,
+};
+
+/// Every fractal that draws with a `Generator`, in the order `list` shows them. Adding one
+/// here is enough for `--fractal` to draw it.
+pub const FRACTALS: &[Fractal] = &[
+    MANDELBROT,
+    JULIA,
+    BURNING_SHIP,
+    COLLATZ,
+    CELTIC,
+    MULTIBROT,
+    PERPENDICULAR,
+    NOVA,
+    EXPONENTIAL,
+    MAGNET,
+    SPIDER,
+    GLYNN,
+];
+
+/// Looks up a fractal by name, e.g. `"burning-ship"`.
+pub fn find(name: &str) -> Result<&'static Fractal, String> {
+    FRACTALS
+        .iter()
+        .find(|fractal| fractal.name == name)
+        .ok_or_else(|| {
+            let names: Vec<_> = FRACTALS.iter().map(|fractal| fractal.name).collect();
+            format!(
+                "unknown fractal {:?}, expected one of {}",
+                name,
+                names.join(", ")
+            )
+        })
+}
+
+#[test]
+fn registry_test() {
+    use crate::Generator;
+    for (i, fractal) in FRACTALS.iter().enumerate() {
+        assert_eq!(find(fractal.name).unwrap().name, fractal.name);
+        assert!(
+            FRACTALS[..i].iter().all(|other| other.name != fractal.name),
+            "{} is registered twice",
+            fractal.name
+        );
+        assert!(fractal.min.0 < fractal.max.0 && fractal.min.1 < fractal.max.1);
+        assert!(fractal.power > 0.0, "{}", fractal.name);
+        // every generator makes orbits to follow
+        let generator = (fractal.generator)(&Parameters::default());
+        assert!(generator.generate(Point(0.1, 0.1)).take(2).count() > 0);
+    }
+    let error = find("mandelbort").unwrap_err();
+    assert!(error.contains("burning-ship"), "{}", error);
+}
@@ -1,23 +1,128 @@
-use crate::fractal::{Grid, Palette, Renderer};
+use crate::{
+    fractal::{
+        boundary, escape_point, EscapeResult, Generator, Grid, Palette, Point, Renderer,
+        SamplePattern, JITTER_SEED,
+    },
+    view::Viewport,
+};
 use colorbrewer::{get_color_ramp, Palette as ColorPalette};
-use pixel_canvas::{Color, Image};
+use pixel_canvas::{Color, Image, XY};
+use rand::{rngs::StdRng, SeedableRng};
 use rayon::prelude::*;
+use std::io;
+
+// colorbrewer can't list its palettes, so they're named here once, as variants. Listing one
+// it doesn't have, or leaving one out of the match, stops this from compiling.
+macro_rules! palettes {
+    ($($palette:ident),* $(,)?) => {
+        /// Every colorbrewer palette, sequential ones first, then diverging and qualitative ones.
+        pub const PALETTE_NAMES: &[&str] = &[$(stringify!($palette)),*];
+
+        fn palette_name(palette: &ColorPalette) -> &'static str {
+            match palette {
+                $(ColorPalette::$palette => stringify!($palette)),*
+            }
+        }
+    };
+}
+
+palettes!(
+    YlGn, YlGnBu, GnBu, BuGn, PuBuGn, PuBu, BuPu, RdPu, PuRd, OrRd, YlOrRd, YlOrBr, Purples, Blues,
+    Greens, Oranges, Reds, Greys, PuOr, BrBG, PRGn, PiYG, RdBu, RdGy, RdYlBu, Spectral, RdYlGn,
+    Accent, Dark2, Paired, Pastel1, Pastel2, Set1, Set2, Set3,
+);
 
 #[derive(Clone, Default)]
 pub struct RGBPalette {
+    name: String,
     ramp: Vec<String>,
+    interior: Color,
 }
 
+// how many colors to take from a ramp unless asked for another number
+const DEFAULT_CLASSES: u32 = 9;
+
 impl RGBPalette {
     pub fn new() -> Self {
-        Self {
-            ramp: get_color_ramp(ColorPalette::OrRd, 9)
-                .unwrap()
-                .into_iter()
-                .map(str::to_owned)
-                .collect(),
+        Self::from_name("OrRd").unwrap()
+    }
+
+    /// Looks up a colorbrewer palette by name, e.g. `"OrRd"` or `"Blues"`, from
+    /// `PALETTE_NAMES`.
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        let ramp = Self::ramp(name, DEFAULT_CLASSES)?
+            .ok_or_else(|| format!("palette {:?} has too few colors", name))?;
+        Ok(Self {
+            name: palette_name(&Self::parse(name)?).to_owned(),
+            ramp,
+            interior: Color::BLACK,
+        })
+    }
+
+    fn parse(name: &str) -> Result<ColorPalette, String> {
+        name.parse().map_err(|_| {
+            format!(
+                "unknown palette {:?}, expected one of {}",
+                name,
+                PALETTE_NAMES.join(", ")
+            )
+        })
+    }
+
+    fn ramp(name: &str, classes: u32) -> Result<Option<Vec<String>>, String> {
+        Ok(get_color_ramp(Self::parse(name)?, classes)
+            .map(|ramp| ramp.into_iter().map(str::to_owned).collect()))
+    }
+
+    /// Takes `classes` colors from the ramp instead of 9. Each colorbrewer palette only comes
+    /// in some sizes, typically 3 to 9 colors for sequential ones, 3 to 11 for diverging ones
+    /// and up to 12 for qualitative ones, and other counts are an error.
+    pub fn with_classes(self, classes: u32) -> Result<Self, String> {
+        match Self::ramp(&self.name, classes)? {
+            Some(ramp) => Ok(Self { ramp, ..self }),
+            None => {
+                let mut sizes = (1..=classes.max(12))
+                    .filter(|&n| matches!(Self::ramp(&self.name, n), Ok(Some(_))));
+                let smallest = sizes.next().unwrap_or(0);
+                let largest = sizes.next_back().unwrap_or(smallest);
+                Err(format!(
+                    "palette {:?} comes with {} to {} colors, not {}",
+                    self.name, smallest, largest, classes
+                ))
+            }
         }
     }
+
+    /// The colors of the underlying ramp, without the reversed half that `get` adds.
+    pub fn colors(&self) -> Vec<Color> {
+        self.ramp
+            .iter()
+            .map(|color_str| color_from_hex(color_str).unwrap())
+            .collect()
+    }
+
+    /// Sets the color used for points that never escape.
+    pub fn with_interior(self, interior: Color) -> Self {
+        Self { interior, ..self }
+    }
+
+    /// The palette's name as `PALETTE_NAMES` lists it.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Parses a color written as `#rrggbb` (the leading `#` is optional).
+pub fn color_from_hex(hex: &str) -> Result<Color, String> {
+    let digits = hex.trim_start_matches('#');
+    if digits.len() != 6 || !digits.is_ascii() {
+        return Err(format!("expected a color like #rrggbb, got {:?}", hex));
+    }
+    let channel = |i: usize| {
+        u8::from_str_radix(&digits[i..i + 2], 16)
+            .map_err(|e| format!("invalid color {:?}: {}", hex, e))
+    };
+    Ok(Color::rgb(channel(0)?, channel(2)?, channel(4)?))
 }
 
 impl Palette for RGBPalette {
@@ -27,34 +132,467 @@ impl Palette for RGBPalette {
     fn get(&self) -> Self::Output {
         let x = self.ramp.clone().into_iter();
         let x2 = x.clone().rev();
-        x.chain(x2).map(|color_str| {
-            let colors: Vec<_> = color_str
-                .chars()
-                .skip(1)
-                .collect::<Vec<_>>()
-                .chunks(2)
-                .map(|chunk| {
-                    let chunks = &[chunk[0] as u8, chunk[1] as u8];
-                    let color_str = String::from_utf8_lossy(chunks);
-                    u8::from_str_radix(&color_str, 16).unwrap()
-                })
-                .collect();
-            let colors: Vec<Color> = colors
-                .chunks(3)
-                .map(|chunk| Color::rgb(chunk[0], chunk[1], chunk[2]))
-                .collect::<Vec<_>>();
-            colors[0]
+        x.chain(x2)
+            .map(|color_str| color_from_hex(&color_str).unwrap())
+    }
+
+    fn interior(&self) -> Color {
+        self.interior
+    }
+}
+
+/// Converts a color's sRGB channels to linear light, each in `0.0..=1.0`, where averaging and
+/// scaling behave the way light does.
+pub fn to_linear(color: Color) -> [f64; 3] {
+    let channel = |c: u8| {
+        let c = f64::from(c) / 255.0;
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    [channel(color.r), channel(color.g), channel(color.b)]
+}
+
+/// Converts linear light back to an sRGB color, clamping any channel that ends up out of
+/// range.
+pub fn from_linear(linear: [f64; 3]) -> Color {
+    from_linear_checked(linear).0
+}
+
+fn from_linear_checked(linear: [f64; 3]) -> (Color, bool) {
+    clamp_channels(linear.map(|l| {
+        let c = if l <= 0.003_130_8 {
+            l * 12.92
+        } else {
+            1.055 * l.powf(1.0 / 2.4) - 0.055
+        };
+        c * 255.0
+    }))
+}
+
+// rounds channels on the 0..=255 scale into a color, and says whether any had to be clamped
+fn clamp_channels(channels: [f64; 3]) -> (Color, bool) {
+    let clipped = channels.iter().any(|c| !(-0.5..255.5).contains(c));
+    let [r, g, b] = channels.map(|c| c.clamp(0.0, 255.0).round() as u8);
+    (Color::rgb(r, g, b), clipped)
+}
+
+// splits colors from the `_checked` blending helpers into the colors and how many of them
+// had to be clamped
+fn count_clipped(checked: Grid<(Color, bool)>) -> (Grid<Color>, usize) {
+    let clipped = checked
+        .0
+        .iter()
+        .flatten()
+        .filter(|&&(_, clipped)| clipped)
+        .count();
+    let colors = checked
+        .0
+        .into_iter()
+        .map(|row| row.into_iter().map(|(color, _)| color).collect())
+        .collect();
+    (Grid(colors), clipped)
+}
+
+fn channels(color: Color) -> [f64; 3] {
+    [color.r, color.g, color.b].map(f64::from)
+}
+
+fn mean(all: impl Iterator<Item = [f64; 3]>) -> [f64; 3] {
+    let (mut total, mut count) = ([0.0; 3], 0.0);
+    for channels in all {
+        for (sum, c) in total.iter_mut().zip(&channels) {
+            *sum += c;
+        }
+        count += 1.0;
+    }
+    total.map(|sum| sum / count)
+}
+
+/// How colors are mixed and darkened: directly on their sRGB channels, which is cheaper and
+/// how everything was drawn before, or in linear light, which keeps blends from looking too
+/// dark in the middle.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Blending {
+    #[default]
+    Gamma,
+    Linear,
+}
+
+impl Blending {
+    /// The color `t` of the way from `from` to `to`.
+    pub fn mix(self, from: Color, to: Color, t: f64) -> Color {
+        self.mix_checked(from, to, t).0
+    }
+
+    /// Like `mix`, also saying whether a channel left `0..=255` and was clamped to the nearest
+    /// end, which can shift the hue away from what the palette asked for.
+    pub fn mix_checked(self, from: Color, to: Color, t: f64) -> (Color, bool) {
+        match self {
+            Blending::Gamma => {
+                let (from, to) = (channels(from), channels(to));
+                clamp_channels([0, 1, 2].map(|i| from[i] + (to[i] - from[i]) * t))
+            }
+            Blending::Linear => {
+                let (from, to) = (to_linear(from), to_linear(to));
+                from_linear_checked([0, 1, 2].map(|i| from[i] + (to[i] - from[i]) * t))
+            }
+        }
+    }
+
+    /// The color with its brightness multiplied by `factor`, clamped to `0.0..=1.0`.
+    pub fn scale(self, color: Color, factor: f64) -> Color {
+        self.scale_checked(color, factor).0
+    }
+
+    /// Like `scale`, also saying whether a channel had to be clamped.
+    pub fn scale_checked(self, color: Color, factor: f64) -> (Color, bool) {
+        let factor = factor.clamp(0.0, 1.0);
+        match self {
+            Blending::Gamma => clamp_channels(channels(color).map(|c| c * factor)),
+            Blending::Linear => from_linear_checked(to_linear(color).map(|c| c * factor)),
+        }
+    }
+
+    /// The average of `colors`, which mustn't be empty.
+    pub fn average(self, colors: &[Color]) -> Color {
+        self.average_checked(colors).0
+    }
+
+    /// Like `average`, also saying whether a channel had to be clamped.
+    pub fn average_checked(self, colors: &[Color]) -> (Color, bool) {
+        match self {
+            Blending::Gamma => clamp_channels(mean(colors.iter().map(|&c| channels(c)))),
+            Blending::Linear => from_linear_checked(mean(colors.iter().map(|&c| to_linear(c)))),
+        }
+    }
+}
+
+/// A palette of `len` colors blended between stops, each a position in `0.0..=1.0` along the
+/// palette and the color found there.
+#[derive(Clone)]
+pub struct GradientPalette {
+    colors: Vec<Color>,
+    interior: Color,
+}
+
+impl GradientPalette {
+    /// The stops must be in order, starting at 0.0 and ending at 1.0.
+    pub fn from_stops(stops: &[(f64, Color)], len: usize) -> Result<Self, String> {
+        Self::from_stops_blended(stops, len, Blending::Gamma)
+    }
+
+    /// Like `from_stops`, mixing the colors between stops with `blending`.
+    pub fn from_stops_blended(
+        stops: &[(f64, Color)],
+        len: usize,
+        blending: Blending,
+    ) -> Result<Self, String> {
+        if stops.len() < 2 || len < 2 {
+            return Err("a gradient needs at least two stops and two colors".to_owned());
+        }
+        if stops[0].0 != 0.0 || stops[stops.len() - 1].0 != 1.0 {
+            return Err("gradient stops must start at 0.0 and end at 1.0".to_owned());
+        }
+        if stops.windows(2).any(|pair| pair[0].0 > pair[1].0) {
+            return Err("gradient stops must be in order".to_owned());
+        }
+
+        let colors = (0..len)
+            .map(|i| {
+                let position = i as f64 / (len - 1) as f64;
+                let after = stops
+                    .iter()
+                    .position(|&(p, _)| p >= position)
+                    .unwrap_or(stops.len() - 1)
+                    .max(1);
+                let ((start, from), (end, to)) = (stops[after - 1], stops[after]);
+                let t = if end > start {
+                    (position - start) / (end - start)
+                } else {
+                    1.0
+                };
+                blending.mix(from, to, t)
+            })
+            .collect();
+        Ok(Self {
+            colors,
+            interior: Color::BLACK,
         })
     }
+
+    /// Sets the color used for points that never escape.
+    pub fn with_interior(self, interior: Color) -> Self {
+        Self { interior, ..self }
+    }
+}
+
+impl Palette for GradientPalette {
+    type Item = Color;
+    type Output = std::vec::IntoIter<Color>;
+
+    fn get(&self) -> Self::Output {
+        self.colors.clone().into_iter()
+    }
+
+    fn interior(&self) -> Color {
+        self.interior
+    }
+}
+
+impl Grid<Color> {
+    /// Shrinks the grid by `factor` in each direction, averaging each `factor` by `factor`
+    /// block of colors into one. Blocks along the right and top edges may be smaller when the
+    /// grid doesn't divide evenly and are averaged over the colors they have.
+    pub fn downsample(&self, factor: usize) -> Result<Grid<Color>, String> {
+        self.downsample_blended(factor, Blending::Gamma)
+    }
+
+    /// Like `downsample`, averaging with `blending`.
+    pub fn downsample_blended(
+        &self,
+        factor: usize,
+        blending: Blending,
+    ) -> Result<Grid<Color>, String> {
+        Ok(self.downsample_checked(factor, blending)?.0)
+    }
+
+    /// Like `downsample_blended`, along with how many of the averages had a channel clamped.
+    pub fn downsample_checked(
+        &self,
+        factor: usize,
+        blending: Blending,
+    ) -> Result<(Grid<Color>, usize), String> {
+        if factor == 0 {
+            return Err("can't downsample by a factor of 0".to_owned());
+        }
+        let rows = self
+            .0
+            .chunks(factor)
+            .map(|rows| {
+                let width = rows[0].len();
+                (0..width)
+                    .step_by(factor)
+                    .map(|x| {
+                        let right = (x + factor).min(width);
+                        let block: Vec<_> = rows
+                            .iter()
+                            .flat_map(|row| &row[x..right])
+                            .copied()
+                            .collect();
+                        blending.average_checked(&block)
+                    })
+                    .collect()
+            })
+            .collect();
+        Ok(count_clipped(Grid(rows)))
+    }
+}
+
+/// Colors results from `sample_escapes` like `color_escapes`, but by their `smooth_count` for
+/// `power` and `bailout`, blending between the two nearest palette colors so that the bands
+/// fade into each other. Also gives how many of the blends had a channel clamped.
+pub fn smooth_color_escapes<P: Palette<Item = Color>>(
+    escapes: &Grid<EscapeResult>,
+    palette: &P,
+    max_iter: usize,
+    power: f64,
+    bailout: f64,
+    blending: Blending,
+) -> (Grid<Color>, usize) {
+    let colors: Vec<_> = palette.get().collect();
+    assert!(!colors.is_empty(), "palettes need at least one color");
+    let (interior, last) = (palette.interior(), colors.len() - 1);
+    count_clipped(
+        escapes.par_map_ref(|escape| match escape.smooth_count(power, bailout) {
+            Some(count) if max_iter > 1 => {
+                let position = (count * last as f64 / (max_iter - 1) as f64).min(last as f64);
+                let below = position.floor() as usize;
+                let above = (below + 1).min(last);
+                blending.mix_checked(colors[below], colors[above], position - below as f64)
+            }
+            Some(_) => (colors[0], false),
+            None => (interior, false),
+        }),
+    )
+}
+
+/// Antialiasing for just the pixels along the edges between escape counts, where aliasing
+/// shows, leaving flat regions with the one point each they were sampled at.
+#[derive(Copy, Clone, Debug)]
+pub struct AdaptiveSampling {
+    threshold: usize,
+    factor: usize,
+    pattern: SamplePattern,
+    blending: Blending,
+    center: Point,
+    angle: f64,
+}
+
+impl AdaptiveSampling {
+    /// Samples the pixels on either side of each edge that `boundary` finds with `threshold`
+    /// again, `factor` by `factor` times in a rotated grid, averaging them on their sRGB values.
+    pub fn new(threshold: usize, factor: usize) -> Self {
+        assert!(factor > 0);
+        Self {
+            threshold,
+            factor,
+            pattern: SamplePattern::RotatedGrid,
+            blending: Blending::Gamma,
+            center: Point(0.0, 0.0),
+            angle: 0.0,
+        }
+    }
+
+    /// Turns the points it samples `angle` radians anticlockwise about `center`, for views
+    /// whose escapes were sampled from a grid turned that way with `Grid::rotated_about`.
+    pub fn with_rotation(self, center: Point, angle: f64) -> Self {
+        Self {
+            center,
+            angle,
+            ..self
+        }
+    }
+
+    /// Lays out the points of each supersampled pixel in `pattern`.
+    pub fn with_pattern(self, pattern: SamplePattern) -> Self {
+        Self { pattern, ..self }
+    }
+
+    /// Averages each supersampled pixel with `blending`.
+    pub fn with_blending(self, blending: Blending) -> Self {
+        Self { blending, ..self }
+    }
+
+    /// The pixels of `escapes` that get supersampled: the ones `boundary` marks, and their
+    /// neighbours above, below and to either side, which take in the far side of each edge.
+    pub fn edges(&self, escapes: &Grid<EscapeResult>, max_iter: usize) -> Grid<bool> {
+        let marked = boundary(escapes, max_iter, self.threshold);
+        let at = |r: usize, c: usize| marked.0.get(r).and_then(|row| row.get(c)) == Some(&true);
+        Grid(
+            (0..marked.0.len())
+                .map(|r| {
+                    (0..marked.0[r].len())
+                        .map(|c| {
+                            at(r, c)
+                                || at(r + 1, c)
+                                || at(r, c + 1)
+                                || (r > 0 && at(r - 1, c))
+                                || (c > 0 && at(r, c - 1))
+                        })
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+
+    /// Colors `escapes`, sampled at the pixels of `viewport` the way `Grid::new` lays them out,
+    /// like `color_escapes` does, except that the pixels along `edges` are sampled again with
+    /// `generator` and averaged. Those come out the same as they would supersampling the whole
+    /// view, except with jittered points, which are seeded per pixel here and not per view.
+    /// Also gives how many of the averages had a channel clamped.
+    pub fn color_escapes<G, P>(
+        &self,
+        generator: &G,
+        palette: &P,
+        max_iter: usize,
+        escapes: &Grid<EscapeResult>,
+        viewport: Viewport,
+    ) -> (Grid<Color>, usize)
+    where
+        G: Generator + Sync,
+        P: Palette<Item = Color> + Sync,
+    {
+        let colors: Vec<_> = palette.get().collect();
+        let interior = palette.interior();
+        let color = |escape: EscapeResult| escape.color(&colors, interior, max_iter, 0);
+        let edges = self.edges(escapes, max_iter);
+        let factor = self.factor;
+        count_clipped(Grid(
+            escapes
+                .0
+                .par_iter()
+                .zip(&edges.0)
+                .enumerate()
+                .map(|(r, (row, edges))| {
+                    row.iter()
+                        .zip(edges)
+                        .enumerate()
+                        .map(|(c, (&escape, &edge))| {
+                            if !edge || factor == 1 {
+                                return (color(escape), false);
+                            }
+                            // seeded by pixel, so that jittered points don't depend on which
+                            // thread gets to them first
+                            let pixel = (r * viewport.width + c) as u64;
+                            let mut rng = StdRng::seed_from_u64(JITTER_SEED ^ pixel);
+                            let mut samples = Vec::with_capacity(factor * factor);
+                            for j in 0..factor {
+                                for i in 0..factor {
+                                    let (x, y) = self.pattern.offset(i, j, factor, &mut rng);
+                                    let p = viewport
+                                        .pixel_to_point(c as f64 + x, r as f64 + y)
+                                        .rotated_about(self.center, self.angle);
+                                    samples.push(color(escape_point(generator, p, max_iter)));
+                                }
+                            }
+                            self.blending.average_checked(&samples)
+                        })
+                        .collect()
+                })
+                .collect(),
+        ))
+    }
+}
+
+// a few points along matplotlib's viridis, which is even in lightness from end to end
+const VIRIDIS: [(f64, u8, u8, u8); 5] = [
+    (0.0, 0x44, 0x01, 0x54),
+    (0.25, 0x3b, 0x52, 0x8b),
+    (0.5, 0x21, 0x91, 0x8c),
+    (0.75, 0x5e, 0xc9, 0x62),
+    (1.0, 0xfd, 0xe7, 0x25),
+];
+
+/// A viridis-style palette of `len` colors from dark purple to yellow, which gets steadily
+/// lighter so that it reads as a scale rather than decoration.
+pub fn viridis(len: usize) -> Result<GradientPalette, String> {
+    let stops: Vec<_> = VIRIDIS
+        .iter()
+        .map(|&(position, r, g, b)| (position, Color::rgb(r, g, b)))
+        .collect();
+    GradientPalette::from_stops_blended(&stops, len, Blending::Linear)
+}
+
+/// Colors results from `sample_escapes` by how many iterations each point cost, from the
+/// start of `viridis` for points that escaped straight away to its end for points that took
+/// all `max_iter`, as the ones that never escaped did.
+pub fn heatmap_escapes(escapes: &Grid<EscapeResult>, max_iter: usize) -> Grid<Color> {
+    let colors = viridis(256).unwrap().colors;
+    let last = colors.len() - 1;
+    escapes.par_map_ref(|escape| {
+        let cost = escape.count.unwrap_or(max_iter).min(max_iter);
+        colors[cost * last / max_iter.max(1)]
+    })
 }
 
 pub struct RGBRenderer<'a> {
     image: &'a mut Image,
+    x: usize,
+    y: usize,
 }
 
 impl<'a> RGBRenderer<'a> {
     pub fn new(image: &'a mut Image) -> Self {
-        Self { image }
+        Self::at(image, 0, 0)
+    }
+
+    /// Writes grids into the image with their lower left corner at (x, y), leaving the
+    /// pixels around them alone.
+    pub fn at(image: &'a mut Image, x: usize, y: usize) -> Self {
+        Self { image, x, y }
     }
 }
 
@@ -62,6 +600,19 @@ impl<'a> Renderer for RGBRenderer<'a> {
     type Item = Color;
 
     fn render(&mut self, grid: Grid<Color>) {
+        let covers_image = self.x == 0
+            && self.y == 0
+            && grid.0.len() == self.image.height()
+            && grid.0.first().map(Vec::len) == Some(self.image.width());
+        if !covers_image {
+            for (r, row) in grid.0.into_iter().enumerate() {
+                for (c, color) in row.into_iter().enumerate() {
+                    self.image[XY(self.x + c, self.y + r)] = color;
+                }
+            }
+            return;
+        }
+
         let width = self.image.width() as usize;
         self.image
             .par_iter_mut()
@@ -73,3 +624,539 @@ impl<'a> Renderer for RGBRenderer<'a> {
             });
     }
 }
+
+/// Writes grids as RGBA bytes into a buffer owned by someone else, such as a texture for
+/// another windowing or GUI library. Rows are `stride` bytes apart and the top row comes
+/// first, as most of them expect, so a grid's last row is written first. Every pixel is
+/// opaque, and parts of a grid past the buffer's width or height are left out.
+pub struct SliceRenderer<'a> {
+    buf: &'a mut [u8],
+    width: usize,
+    height: usize,
+    stride: usize,
+}
+
+impl<'a> SliceRenderer<'a> {
+    pub fn new(
+        buf: &'a mut [u8],
+        width: usize,
+        height: usize,
+        stride: usize,
+    ) -> Result<Self, String> {
+        if stride < width * 4 {
+            return Err(format!(
+                "a stride of {} bytes can't hold {} RGBA pixels",
+                stride, width
+            ));
+        }
+        let needed = match height {
+            0 => 0,
+            _ => stride * (height - 1) + width * 4,
+        };
+        if buf.len() < needed {
+            return Err(format!(
+                "{}x{} pixels with a stride of {} need {} bytes, but the buffer has {}",
+                width,
+                height,
+                stride,
+                needed,
+                buf.len()
+            ));
+        }
+        Ok(Self {
+            buf,
+            width,
+            height,
+            stride,
+        })
+    }
+}
+
+impl<'a> Renderer for SliceRenderer<'a> {
+    type Item = Color;
+
+    fn render(&mut self, grid: Grid<Color>) {
+        for (r, row) in grid.0.iter().take(self.height).enumerate() {
+            let start = (self.height - 1 - r) * self.stride;
+            let line = &mut self.buf[start..start + self.width * 4];
+            for (pixel, color) in line.chunks_exact_mut(4).zip(row) {
+                pixel.copy_from_slice(&[color.r, color.g, color.b, 255]);
+            }
+        }
+    }
+}
+
+/// Wraps another renderer and darkens each color by the matching factor in a grid of light,
+/// such as one from `lambert_light`, where 1.0 leaves the color as it was and 0.0 makes it
+/// black.
+pub struct LitRenderer<R> {
+    renderer: R,
+    light: Grid<f64>,
+    blending: Blending,
+    clipped: usize,
+}
+
+impl<R: Renderer<Item = Color>> LitRenderer<R> {
+    pub fn new(renderer: R, light: Grid<f64>) -> Self {
+        Self {
+            renderer,
+            light,
+            blending: Blending::Gamma,
+            clipped: 0,
+        }
+    }
+
+    /// Darkens in linear light or on the sRGB channels, the default.
+    pub fn with_blending(self, blending: Blending) -> Self {
+        Self { blending, ..self }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.renderer
+    }
+}
+
+impl<R: Renderer<Item = Color>> Renderer for LitRenderer<R> {
+    type Item = Color;
+
+    fn render(&mut self, grid: Grid<Color>) {
+        let (light, blending) = (&self.light, self.blending);
+        let lit = grid
+            .0
+            .into_iter()
+            .zip(&light.0)
+            .map(|(row, factors)| {
+                row.into_iter()
+                    .zip(factors)
+                    .map(|(color, &factor)| blending.scale_checked(color, factor))
+                    .collect()
+            })
+            .collect();
+        let (lit, clipped) = count_clipped(Grid(lit));
+        self.clipped += clipped;
+        self.renderer.render(lit);
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.renderer.finish()
+    }
+
+    fn clipped(&self) -> usize {
+        self.clipped + self.renderer.clipped()
+    }
+}
+
+/// Wraps another renderer and brightens each color toward white by the matching strength in a
+/// grid of glow, such as one from `glow`, where 0.0 leaves the color as it was and 1.0 makes it
+/// white.
+pub struct GlowRenderer<R> {
+    renderer: R,
+    glow: Grid<f64>,
+    blending: Blending,
+    clipped: usize,
+}
+
+impl<R: Renderer<Item = Color>> GlowRenderer<R> {
+    pub fn new(renderer: R, glow: Grid<f64>) -> Self {
+        Self {
+            renderer,
+            glow,
+            blending: Blending::Gamma,
+            clipped: 0,
+        }
+    }
+
+    /// Brightens in linear light or on the sRGB channels, the default.
+    pub fn with_blending(self, blending: Blending) -> Self {
+        Self { blending, ..self }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.renderer
+    }
+}
+
+impl<R: Renderer<Item = Color>> Renderer for GlowRenderer<R> {
+    type Item = Color;
+
+    fn render(&mut self, grid: Grid<Color>) {
+        let (glow, blending) = (&self.glow, self.blending);
+        let white = Color::rgb(255, 255, 255);
+        let glowing = grid
+            .0
+            .into_iter()
+            .zip(&glow.0)
+            .map(|(row, strengths)| {
+                row.into_iter()
+                    .zip(strengths)
+                    .map(|(color, &strength)| {
+                        blending.mix_checked(color, white, strength.clamp(0.0, 1.0))
+                    })
+                    .collect()
+            })
+            .collect();
+        let (glowing, clipped) = count_clipped(Grid(glowing));
+        self.clipped += clipped;
+        self.renderer.render(glowing);
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.renderer.finish()
+    }
+
+    fn clipped(&self) -> usize {
+        self.clipped + self.renderer.clipped()
+    }
+}
+
+/// Wraps another renderer and shrinks each grid by `factor` before passing it on, averaging
+/// blocks of colors, for drawing a supersampled grid at its final size.
+pub struct DownsampleRenderer<R> {
+    renderer: R,
+    factor: usize,
+    blending: Blending,
+    clipped: usize,
+}
+
+impl<R: Renderer<Item = Color>> DownsampleRenderer<R> {
+    pub fn new(renderer: R, factor: usize) -> Self {
+        assert!(factor > 0, "can't downsample by a factor of 0");
+        Self {
+            renderer,
+            factor,
+            blending: Blending::Gamma,
+            clipped: 0,
+        }
+    }
+
+    /// Averages in linear light or on the sRGB channels, the default.
+    pub fn with_blending(self, blending: Blending) -> Self {
+        Self { blending, ..self }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.renderer
+    }
+}
+
+impl<R: Renderer<Item = Color>> Renderer for DownsampleRenderer<R> {
+    type Item = Color;
+
+    fn render(&mut self, grid: Grid<Color>) {
+        if self.factor == 1 {
+            return self.renderer.render(grid);
+        }
+        let (small, clipped) = grid.downsample_checked(self.factor, self.blending).unwrap();
+        self.clipped += clipped;
+        self.renderer.render(small);
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.renderer.finish()
+    }
+
+    fn clipped(&self) -> usize {
+        self.clipped + self.renderer.clipped()
+    }
+}
+
+#[test]
+fn color_from_hex_test() {
+    let color = color_from_hex("#fdbb84").unwrap();
+    assert_eq!((color.r, color.g, color.b), (0xfd, 0xbb, 0x84));
+    assert!(color_from_hex("fdbb8").is_err());
+    assert!(color_from_hex("#fdbbzz").is_err());
+}
+
+#[test]
+fn with_classes_test() {
+    assert_eq!(RGBPalette::new().colors().len(), 9);
+    let blues = RGBPalette::from_name("Blues").unwrap();
+    assert_eq!(blues.clone().with_classes(3).unwrap().colors().len(), 3);
+    assert_eq!(
+        RGBPalette::from_name("RdYlBu")
+            .unwrap()
+            .with_classes(11)
+            .unwrap()
+            .get()
+            .count(),
+        22
+    );
+    assert_eq!(
+        blues.with_classes(12).err().unwrap(),
+        "palette \"Blues\" comes with 3 to 9 colors, not 12"
+    );
+}
+
+#[test]
+fn palette_names_test() {
+    for name in PALETTE_NAMES {
+        assert_eq!(palette_name(&RGBPalette::parse(name).unwrap()), *name);
+        // some qualitative palettes stop short of the usual 9 colors, but all have 3
+        assert!(matches!(RGBPalette::ramp(name, 3), Ok(Some(_))), "{}", name);
+    }
+    let unknown = RGBPalette::from_name("Rainbow").err().unwrap();
+    assert!(
+        unknown.contains("expected one of YlGn, YlGnBu"),
+        "{}",
+        unknown
+    );
+}
+
+#[test]
+fn slice_renderer_test() {
+    // 2x2 pixels, with a spare pixel at the end of each row
+    let mut buf = vec![0; 3 * 4 + 2 * 4];
+    let grid = Grid(vec![
+        vec![Color::rgb(1, 2, 3), Color::rgb(4, 5, 6)],
+        vec![Color::rgb(7, 8, 9), Color::rgb(10, 11, 12)],
+    ]);
+    SliceRenderer::new(&mut buf, 2, 2, 12).unwrap().render(grid);
+    #[rustfmt::skip]
+    assert_eq!(buf, vec![
+        7, 8, 9, 255, 10, 11, 12, 255, 0, 0, 0, 0,
+        1, 2, 3, 255, 4, 5, 6, 255,
+    ]);
+
+    assert!(SliceRenderer::new(&mut buf, 2, 2, 7).is_err());
+    assert!(SliceRenderer::new(&mut buf, 2, 3, 12).is_err());
+    assert!(SliceRenderer::new(&mut [], 0, 0, 0).is_ok());
+}
+
+#[test]
+fn gradient_palette_test() {
+    let stops = [
+        (0.0, Color::rgb(0, 0, 0)),
+        (0.5, Color::rgb(200, 100, 0)),
+        (1.0, Color::rgb(200, 100, 100)),
+    ];
+    let colors: Vec<_> = GradientPalette::from_stops(&stops, 5)
+        .unwrap()
+        .get()
+        .map(|c| (c.r, c.g, c.b))
+        .collect();
+    assert_eq!(
+        colors,
+        vec![
+            (0, 0, 0),
+            (100, 50, 0),
+            (200, 100, 0),
+            (200, 100, 50),
+            (200, 100, 100)
+        ]
+    );
+    assert!(GradientPalette::from_stops(&stops[..2], 5).is_err());
+    assert!(GradientPalette::from_stops(&[stops[0], stops[2], stops[1]], 5).is_err());
+}
+
+#[test]
+fn linear_blending_test() {
+    for &c in &[0, 1, 10, 128, 200, 255] {
+        let color = Color::rgb(c, c, c);
+        assert_eq!(from_linear(to_linear(color)).r, c);
+    }
+    let (black, white) = (Color::rgb(0, 0, 0), Color::rgb(255, 255, 255));
+    assert_eq!(Blending::Gamma.mix(black, white, 0.5).r, 128);
+    // half as much light is much brighter than half the encoded value
+    assert_eq!(Blending::Linear.mix(black, white, 0.5).r, 188);
+    assert_eq!(Blending::Linear.scale(white, 0.5).r, 188);
+    assert_eq!(Blending::Linear.scale(white, 2.0).r, 255);
+
+    let stops = [(0.0, black), (1.0, white)];
+    let linear = GradientPalette::from_stops_blended(&stops, 3, Blending::Linear).unwrap();
+    let grays: Vec<_> = linear.get().map(|c| c.r).collect();
+    assert_eq!(grays, vec![0, 188, 255]);
+}
+
+#[test]
+fn downsample_test() {
+    let gray = |c| Color::rgb(c, c, c);
+    let grid = Grid(vec![
+        vec![gray(0), gray(10), gray(20)],
+        vec![gray(30), gray(40), gray(50)],
+        vec![gray(60), gray(70), gray(80)],
+    ]);
+    let reds = |grid: Grid<Color>| -> Vec<Vec<u8>> {
+        grid.0
+            .into_iter()
+            .map(|row| row.into_iter().map(|c| c.r).collect())
+            .collect()
+    };
+    assert_eq!(
+        reds(grid.downsample(1).unwrap()),
+        vec![vec![0, 10, 20], vec![30, 40, 50], vec![60, 70, 80]]
+    );
+    // the uneven edges average only the colors they cover
+    assert_eq!(
+        reds(grid.downsample(2).unwrap()),
+        vec![vec![20, 35], vec![65, 80]]
+    );
+    assert_eq!(reds(grid.downsample(5).unwrap()), vec![vec![40]]);
+    assert!(grid.downsample(0).is_err());
+    assert!(Grid(vec![]).downsample(2).unwrap().0.is_empty());
+
+    let checker = Grid(vec![vec![gray(0), gray(255)]]);
+    assert_eq!(
+        reds(checker.downsample_blended(2, Blending::Linear).unwrap()),
+        vec![vec![188]]
+    );
+}
+
+#[test]
+fn clamp_channels_test() {
+    let (color, clipped) = clamp_channels([0.0, 127.6, 255.4]);
+    assert_eq!(
+        ((color.r, color.g, color.b), clipped),
+        ((0, 128, 255), false)
+    );
+    let (color, clipped) = clamp_channels([-3.0, 10.0, 300.0]);
+    assert_eq!(((color.r, color.g, color.b), clipped), ((0, 10, 255), true));
+    assert!(clamp_channels([f64::NAN, 0.0, 0.0]).1);
+    // past the ends of a gradient the channels overshoot
+    let (black, white) = (Color::rgb(0, 0, 0), Color::rgb(255, 255, 255));
+    let (color, clipped) = Blending::Gamma.mix_checked(black, white, 1.5);
+    assert_eq!((color.r, clipped), (255, true));
+    assert!(!Blending::Linear.mix_checked(black, white, 0.5).1);
+
+    // renderers count what they clamp along with whatever they pass the colors on to
+    let mut renderer = LitRenderer::new(
+        DownsampleRenderer::new(crate::export::PngRenderer::new(), 1),
+        Grid(vec![vec![1.0, 0.5]]),
+    );
+    renderer.render(Grid(vec![vec![white, white]]));
+    assert_eq!(renderer.clipped(), 0);
+}
+
+#[test]
+fn smooth_color_escapes_test() {
+    use crate::fractal::Point;
+    let palette =
+        GradientPalette::from_stops(&[(0.0, Color::BLACK), (1.0, Color::WHITE)], 3).unwrap();
+    let escape = |count, r| EscapeResult {
+        count,
+        last: Point(r, 0.0),
+    };
+    // 5 iterations over 3 colors: a count of 1 lands halfway between the first two
+    let escapes = Grid(vec![vec![
+        escape(Some(1), 10.0),
+        escape(Some(2), 10.0),
+        escape(Some(2), 100.0),
+        escape(None, 0.0),
+    ]]);
+    let (colors, clipped) = smooth_color_escapes(
+        &escapes,
+        &palette.with_interior(Color::rgb(1, 2, 3)),
+        5,
+        2.0,
+        10.0,
+        Blending::Gamma,
+    );
+    assert_eq!(clipped, 0);
+    let reds: Vec<_> = colors.0[0].iter().map(|c| (c.r, c.g)).collect();
+    assert_eq!(reds, vec![(64, 64), (128, 128), (64, 64), (1, 2)]);
+}
+
+#[test]
+fn heatmap_escapes_test() {
+    use crate::fractal::Point;
+    let escape = |count| EscapeResult {
+        count,
+        last: Point(0.0, 0.0),
+    };
+    let escapes = Grid(vec![vec![
+        escape(Some(0)),
+        escape(Some(5)),
+        escape(Some(10)),
+        escape(None),
+    ]]);
+    let colors: Vec<_> = heatmap_escapes(&escapes, 10).0[0]
+        .iter()
+        .map(|c| (c.r, c.g, c.b))
+        .collect();
+    assert_eq!(colors[0], (0x44, 0x01, 0x54));
+    assert_eq!(colors[2], (0xfd, 0xe7, 0x25));
+    // never escaping costs as much as the most iterations do
+    assert_eq!(colors[3], colors[2]);
+    // and the scale gets lighter all the way along
+    let lightness = |(r, g, b): (u8, u8, u8)| u32::from(r) + u32::from(g) + u32::from(b);
+    assert!(lightness(colors[0]) < lightness(colors[1]));
+    assert!(lightness(colors[1]) < lightness(colors[2]));
+}
+
+#[test]
+fn adaptive_sampling_test() {
+    use crate::{color_escapes, mandelbrot::Mandelbrot, sample_escapes};
+    let escape = |count| EscapeResult {
+        count: Some(count),
+        last: Point(0.0, 0.0),
+    };
+    // the step between 1 and 9 is marked on the 1, and the 9 beside it comes along
+    let steps = Grid(vec![vec![
+        escape(1),
+        escape(1),
+        escape(9),
+        escape(9),
+        escape(9),
+    ]]);
+    assert_eq!(
+        AdaptiveSampling::new(2, 2).edges(&steps, 10).0,
+        vec![vec![true, true, true, false, false]]
+    );
+
+    let (min, max) = (Point(-2.0, -1.0), Point(1.0, 1.0));
+    let viewport = Viewport {
+        min,
+        max,
+        width: 24,
+        height: 16,
+    };
+    let mandelbrot = Mandelbrot::new(Point(0.0, 0.0));
+    let palette = RGBPalette::new();
+    let escapes = sample_escapes(&Grid::new(24, 16, min, max), &mandelbrot, 20);
+    let plain = color_escapes(&escapes, &palette, 20, 0);
+    let supersampled = Grid::supersampled_in_pattern(24, 16, min, max, 3, SamplePattern::Grid);
+    let everywhere = color_escapes(
+        &sample_escapes(&supersampled, &mandelbrot, 20),
+        &palette,
+        20,
+        0,
+    )
+    .downsample(3)
+    .unwrap();
+    let adaptive = AdaptiveSampling::new(1, 3).with_pattern(SamplePattern::Grid);
+    let edges = adaptive.edges(&escapes, 20);
+    let (colors, _) = adaptive.color_escapes(&mandelbrot, &palette, 20, &escapes, viewport);
+    let rgb = |c: &Color| (c.r, c.g, c.b);
+    let marked = edges.0.iter().flatten().filter(|&&edge| edge).count();
+    assert!(0 < marked && marked < 24 * 16);
+    for r in 0..16 {
+        for c in 0..24 {
+            let expected = if edges.0[r][c] { &everywhere } else { &plain };
+            assert_eq!(rgb(&colors.0[r][c]), rgb(&expected.0[r][c]), "{}, {}", c, r);
+        }
+    }
+
+    // turned views resample their edges at the same turned points as supersampling them whole
+    let (center, angle) = (Point(-0.5, 0.0), 0.5);
+    let grid = Grid::new(24, 16, min, max).rotated_about(center, angle);
+    let escapes = sample_escapes(&grid, &mandelbrot, 20);
+    let plain = color_escapes(&escapes, &palette, 20, 0);
+    let supersampled = supersampled.rotated_about(center, angle);
+    let everywhere = color_escapes(
+        &sample_escapes(&supersampled, &mandelbrot, 20),
+        &palette,
+        20,
+        0,
+    )
+    .downsample(3)
+    .unwrap();
+    let adaptive = adaptive.with_rotation(center, angle);
+    let edges = adaptive.edges(&escapes, 20);
+    let (colors, _) = adaptive.color_escapes(&mandelbrot, &palette, 20, &escapes, viewport);
+    for r in 0..16 {
+        for c in 0..24 {
+            let expected = if edges.0[r][c] { &everywhere } else { &plain };
+            assert_eq!(rgb(&colors.0[r][c]), rgb(&expected.0[r][c]), "{}, {}", c, r);
+        }
+    }
+}
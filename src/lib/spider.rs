@@ -0,0 +1,52 @@
+use crate::fractal::{Generator, Point};
+
+/// The spider fractal, where the constant moves along with the orbit:
+/// `z -> z^2 + c` and then `c -> c/2 + z`, starting from `z = 0` with `c` at the point.
+///
+/// Halving `c` each step lets the orbit forget where it started, so the set grows thin
+/// legs instead of the Mandelbrot set's bulbs.
+pub struct Spider;
+
+impl Spider {
+    pub fn new() -> Self {
+        Self
+    }
+
+    fn next((z, c): (Point, Point)) -> (Point, Point) {
+        let z = z * z + c;
+        (z, c * 0.5 + z)
+    }
+}
+
+impl Default for Spider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Generator for Spider {
+    type Output = impl Iterator<Item = Point>;
+
+    fn generate(&self, p: Point) -> Self::Output {
+        (0u32..).scan((Point(0.0, 0.0), p), |state, _| {
+            *state = Spider::next(*state);
+            Some(state.0)
+        })
+    }
+}
+
+#[test]
+fn spider_test() {
+    let spider = Spider::new();
+    // at c = 1 the orbit is 1, then 1 + 1.5 = 2.5, then 6.25 + 3.25 = 9.5
+    let orbit: Vec<_> = spider.generate(Point(1.0, 0.0)).take(3).collect();
+    assert_eq!(
+        orbit.iter().map(|z| z.0).collect::<Vec<_>>(),
+        vec![1.0, 2.5, 9.5]
+    );
+    // while from c = -0.5 both keep moving but stay small
+    assert!(spider
+        .generate(Point(-0.5, 0.0))
+        .take(200)
+        .all(|z| !spider.escaped(z)));
+}
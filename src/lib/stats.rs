@@ -0,0 +1,149 @@
+use crate::fractal::{EscapeResult, Grid};
+use std::fmt;
+
+// width of the longest histogram bar, in characters
+const BAR_WIDTH: usize = 40;
+
+/// How many iterations the points of a view took to escape, for judging whether `max_iter` is
+/// high enough before rendering.
+#[derive(Debug, PartialEq)]
+pub struct EscapeStats {
+    pub points: usize,
+    pub inside: usize,
+    /// The fewest and most steps any escaping point stayed close for, if any escaped.
+    pub range: Option<(usize, usize)>,
+    pub mean: f64,
+    /// How many escaping points fall in each of a run of equal ranges of counts, starting at 0
+    /// and ending at `max_iter`.
+    pub histogram: Vec<usize>,
+    /// How many colors had a channel clamped while drawing the view, if it was drawn.
+    pub clipped: Option<usize>,
+    max_iter: usize,
+}
+
+impl EscapeStats {
+    /// Aggregates results from `sample_escapes`, sorting the escape counts into `buckets`
+    /// equal ranges.
+    pub fn new(escapes: &Grid<EscapeResult>, max_iter: usize, buckets: usize) -> Self {
+        let buckets = buckets.clamp(1, max_iter.max(1));
+        let mut histogram = vec![0; buckets];
+        let (mut points, mut inside, mut total) = (0, 0, 0);
+        let mut range: Option<(usize, usize)> = None;
+        for escape in escapes.0.iter().flatten() {
+            points += 1;
+            let count = match escape.count {
+                Some(count) => count,
+                None => {
+                    inside += 1;
+                    continue;
+                }
+            };
+            total += count;
+            range = Some(range.map_or((count, count), |(min, max)| {
+                (min.min(count), max.max(count))
+            }));
+            histogram[(count * buckets / max_iter.max(1)).min(buckets - 1)] += 1;
+        }
+        let escaped = points - inside;
+        EscapeStats {
+            points,
+            inside,
+            range,
+            mean: if escaped > 0 {
+                total as f64 / escaped as f64
+            } else {
+                0.0
+            },
+            histogram,
+            clipped: None,
+            max_iter,
+        }
+    }
+
+    /// Adds how many colors had a channel clamped while drawing the view, as
+    /// `Renderer::clipped` counts them.
+    pub fn with_clipped(self, clipped: usize) -> Self {
+        Self {
+            clipped: Some(clipped),
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for EscapeStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let percent = |n: usize| 100.0 * n as f64 / self.points.max(1) as f64;
+        writeln!(f, "points: {}", self.points)?;
+        writeln!(
+            f,
+            "inside after {} iterations: {} ({:.1}%)",
+            self.max_iter,
+            self.inside,
+            percent(self.inside)
+        )?;
+        match self.range {
+            Some((min, max)) => writeln!(
+                f,
+                "escape counts: min {}, max {}, mean {:.1}",
+                min, max, self.mean
+            )?,
+            None => writeln!(f, "no points escaped")?,
+        }
+        if let Some(clipped) = self.clipped {
+            writeln!(f, "colors clamped into 0-255: {}", clipped)?;
+        }
+        let largest = self.histogram.iter().max().copied().unwrap_or(0).max(1);
+        let buckets = self.histogram.len();
+        for (i, &count) in self.histogram.iter().enumerate() {
+            let (start, end) = (
+                i * self.max_iter / buckets,
+                (i + 1) * self.max_iter / buckets,
+            );
+            writeln!(
+                f,
+                "{:>6}..{:<6} {:<width$} {} ({:.1}%)",
+                start,
+                end,
+                "#".repeat(count * BAR_WIDTH / largest),
+                count,
+                percent(count),
+                width = BAR_WIDTH
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn escape_stats_test() {
+    use crate::fractal::Point;
+    let escape = |count| EscapeResult {
+        count,
+        last: Point(0.0, 0.0),
+    };
+    let escapes = Grid(vec![
+        vec![escape(Some(1)), escape(Some(2)), escape(None)],
+        vec![escape(Some(9)), escape(None), escape(Some(4))],
+    ]);
+    let stats = EscapeStats::new(&escapes, 10, 2);
+    assert_eq!(stats.points, 6);
+    assert_eq!(stats.inside, 2);
+    assert_eq!(stats.range, Some((1, 9)));
+    assert_eq!(stats.mean, 4.0);
+    assert_eq!(stats.histogram, vec![3, 1]);
+    let text = stats.to_string();
+    assert!(
+        text.contains("inside after 10 iterations: 2 (33.3%)"),
+        "{}",
+        text
+    );
+    assert!(text.contains("min 1, max 9, mean 4.0"), "{}", text);
+    assert!(!text.contains("clamped"));
+    assert!(stats
+        .with_clipped(3)
+        .to_string()
+        .contains("clamped into 0-255: 3"));
+
+    let none_escaped = EscapeStats::new(&Grid(vec![vec![escape(None)]]), 10, 4);
+    assert_eq!((none_escaped.range, none_escaped.mean), (None, 0.0));
+}
@@ -0,0 +1,197 @@
+use crate::fractal::{Generator, Grid, Point, Renderer};
+use pixel_canvas::Color;
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+/// An orbit trap shaped like a picture: a square of the plane `size` wide, centred on
+/// `center`, with a texture stretched over it. Each point is colored by the texel under the
+/// step of its orbit that comes closest to the centre, which scatters copies of the picture
+/// through the set and around the boundary.
+pub struct TextureTrap {
+    // bottom row first, like every other grid of colors
+    texture: Grid<Color>,
+    center: Point,
+    size: f64,
+}
+
+impl TextureTrap {
+    pub fn new(texture: Grid<Color>, center: Point, size: f64) -> Result<Self, String> {
+        if texture.0.first().is_none_or(Vec::is_empty) {
+            return Err("the trap texture has no pixels".to_owned());
+        }
+        if !(size > 0.0 && size.is_finite()) {
+            return Err(format!(
+                "the trap has to have a positive size, not {}",
+                size
+            ));
+        }
+        Ok(Self {
+            texture,
+            center,
+            size,
+        })
+    }
+
+    /// Reads the texture from a PNG file.
+    pub fn load(path: &Path, center: Point, size: f64) -> Result<Self, String> {
+        let file =
+            File::open(path).map_err(|e| format!("couldn't open {}: {}", path.display(), e))?;
+        let texture =
+            read_png(file).map_err(|e| format!("couldn't read {}: {}", path.display(), e))?;
+        TextureTrap::new(texture, center, size)
+    }
+
+    /// The texel under the closest approach to the centre of `p`'s orbit, following it for at
+    /// most `max_iter` steps or until it escapes, or `None` if that step misses the texture.
+    pub fn texel<G: Generator>(&self, generator: &G, p: Point, max_iter: usize) -> Option<Color> {
+        let closest = generator
+            .generate(p)
+            .take(max_iter)
+            .take_while(|&z| z.is_finite() && !generator.escaped(z))
+            .map(|z| z - self.center)
+            .min_by(|a, b| a.magnitude_squared().total_cmp(&b.magnitude_squared()))?;
+        let (height, width) = (self.texture.0.len(), self.texture.0[0].len());
+        let texel = |offset: f64, len: usize| {
+            let i = ((offset / self.size + 0.5) * len as f64).floor();
+            if (0.0..len as f64).contains(&i) {
+                Some(i as usize)
+            } else {
+                None
+            }
+        };
+        let (x, y) = (texel(closest.0, width)?, texel(closest.1, height)?);
+        Some(self.texture.0[y][x])
+    }
+}
+
+/// Decodes a PNG into a grid of colors with the bottom row first, dropping any transparency.
+pub fn read_png<R: Read>(r: R) -> Result<Grid<Color>, String> {
+    let mut decoder = png::Decoder::new(r);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(|e| e.to_string())?;
+    let mut data = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut data).map_err(|e| e.to_string())?;
+    let pixel: fn(&[u8]) -> Color = match info.color_type {
+        png::ColorType::Grayscale | png::ColorType::GrayscaleAlpha => {
+            |p| Color::rgb(p[0], p[0], p[0])
+        }
+        png::ColorType::Rgb | png::ColorType::Rgba => |p| Color::rgb(p[0], p[1], p[2]),
+        png::ColorType::Indexed => return Err("palette images weren't expanded".to_owned()),
+    };
+    let channels = info.color_type.samples();
+    let rows = data[..info.buffer_size()]
+        .chunks(info.line_size)
+        .rev()
+        .map(|row| {
+            row.chunks(channels)
+                .take(info.width as usize)
+                .map(pixel)
+                .collect()
+        })
+        .collect();
+    Ok(Grid(rows))
+}
+
+/// Wraps another renderer and replaces each color that has a texel in a grid of them, such
+/// as `TextureTrap::texel` gives, leaving the rest as they were.
+pub struct TrapRenderer<R> {
+    renderer: R,
+    texels: Grid<Option<Color>>,
+}
+
+impl<R: Renderer<Item = Color>> TrapRenderer<R> {
+    pub fn new(renderer: R, texels: Grid<Option<Color>>) -> Self {
+        Self { renderer, texels }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.renderer
+    }
+}
+
+impl<R: Renderer<Item = Color>> Renderer for TrapRenderer<R> {
+    type Item = Color;
+
+    fn render(&mut self, grid: Grid<Color>) {
+        let trapped = grid
+            .0
+            .into_iter()
+            .zip(&self.texels.0)
+            .map(|(row, texels)| {
+                row.into_iter()
+                    .zip(texels)
+                    .map(|(color, texel)| texel.unwrap_or(color))
+                    .collect()
+            })
+            .collect();
+        self.renderer.render(Grid(trapped));
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.renderer.finish()
+    }
+
+    fn clipped(&self) -> usize {
+        self.renderer.clipped()
+    }
+}
+
+#[test]
+fn texture_trap_test() {
+    use crate::mandelbrot::Mandelbrot;
+    // red and green along the bottom, blue and white along the top
+    let texture = Grid(vec![
+        vec![Color::rgb(255, 0, 0), Color::rgb(0, 255, 0)],
+        vec![Color::rgb(0, 0, 255), Color::rgb(255, 255, 255)],
+    ]);
+    let trap = TextureTrap::new(texture, Point(0.0, 0.0), 1.0).unwrap();
+    let mandelbrot = Mandelbrot::new(Point(0.0, 0.0));
+    let texel = |p| {
+        trap.texel(&mandelbrot, p, 50)
+            .map(|Color { r, g, b }| (r, g, b))
+    };
+    // the orbit of c stays at c for a step or two, so small ones land on their own quarter
+    assert_eq!(texel(Point(-0.1, -0.1)), Some((255, 0, 0)));
+    assert_eq!(texel(Point(0.1, -0.1)), Some((0, 255, 0)));
+    assert_eq!(texel(Point(-0.1, 0.1)), Some((0, 0, 255)));
+    // the orbit of -2 goes 2, 2, ... and never gets within half of the centre
+    assert_eq!(texel(Point(-2.0, 0.0)), None);
+    // escaping straight away leaves nothing to trap
+    assert_eq!(texel(Point(20.0, 0.0)), None);
+
+    assert!(TextureTrap::new(Grid(vec![]), Point(0.0, 0.0), 1.0).is_err());
+    assert!(TextureTrap::new(Grid(vec![vec![Color::WHITE]]), Point(0.0, 0.0), 0.0).is_err());
+}
+
+#[test]
+fn read_png_test() {
+    use crate::export::PngRenderer;
+    let grid = Grid(vec![
+        vec![
+            Color::rgb(1, 2, 3),
+            Color::rgb(4, 5, 6),
+            Color::rgb(7, 8, 9),
+        ],
+        vec![
+            Color::rgb(10, 11, 12),
+            Color::rgb(13, 14, 15),
+            Color::rgb(16, 17, 18),
+        ],
+    ]);
+    let mut png = PngRenderer::new();
+    png.render(Grid(grid.0.clone()));
+    let mut bytes = vec![];
+    png.write(&mut bytes).unwrap();
+    let read = read_png(&bytes[..]).unwrap();
+    let rgb = |grid: &Grid<Color>| -> Vec<Vec<(u8, u8, u8)>> {
+        grid.0
+            .iter()
+            .map(|row| row.iter().map(|c| (c.r, c.g, c.b)).collect())
+            .collect()
+    };
+    assert_eq!(rgb(&read), rgb(&grid));
+    assert!(read_png(&b"not a png"[..]).is_err());
+}
@@ -0,0 +1,327 @@
+use crate::fractal::{sample_escapes, Generator, Grid, Point};
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+/// The corners of a region of the complex plane.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+pub struct Bounds {
+    pub min: Point,
+    pub max: Point,
+}
+
+/// Written as `min_re,min_im,max_re,max_im`, the same as it's parsed.
+impl fmt::Display for Bounds {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Bounds {
+            min: Point(x0, y0),
+            max: Point(x1, y1),
+        } = self;
+        write!(f, "{},{},{},{}", x0, y0, x1, y1)
+    }
+}
+
+/// Reads bounds like `-2.25,-1.5,0.75,1.5`, the real and imaginary parts of `min` and then
+/// of `max`, which must be above and to the right of `min`.
+impl FromStr for Bounds {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Bounds, String> {
+        let parts = s
+            .split(',')
+            .map(|part| part.trim().parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("invalid bounds {:?}: {}", s, e))?;
+        let (min, max) = match parts[..] {
+            [x0, y0, x1, y1] => (Point(x0, y0), Point(x1, y1)),
+            _ => {
+                return Err(format!(
+                    "expected bounds like -2.25,-1.5,0.75,1.5, got {:?}",
+                    s
+                ))
+            }
+        };
+        if min.0 >= max.0 || min.1 >= max.1 {
+            return Err(format!("bounds {:?} don't cover any area", s));
+        }
+        Ok(Bounds { min, max })
+    }
+}
+
+/// A region of the complex plane sampled on a `width` by `height` grid of pixels. Pixel (0, 0)
+/// lands on `min` and pixel (width - 1, height - 1) on `max`, the same as `Grid::new`.
+#[derive(Copy, Clone, Debug)]
+pub struct Viewport {
+    pub min: Point,
+    pub max: Point,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Viewport {
+    /// The distance between neighbouring pixels along each axis.
+    pub fn pixel_size(&self) -> (f64, f64) {
+        (
+            (self.max.0 - self.min.0) / (self.width - 1) as f64,
+            (self.max.1 - self.min.1) / (self.height - 1) as f64,
+        )
+    }
+
+    /// The point at a pixel position, which can be fractional or off the grid.
+    pub fn pixel_to_point(&self, x: f64, y: f64) -> Point {
+        let (x_spread, y_spread) = self.pixel_size();
+        Point(x_spread * x + self.min.0, y_spread * y + self.min.1)
+    }
+
+    /// The pixel position of a point, the inverse of `pixel_to_point`.
+    pub fn point_to_pixel(&self, Point(x, y): Point) -> (f64, f64) {
+        let (x_spread, y_spread) = self.pixel_size();
+        ((x - self.min.0) / x_spread, (y - self.min.1) / y_spread)
+    }
+}
+
+impl Bounds {
+    /// The bounds reaching `half_width` either side of `center` and `half_height` above and
+    /// below it.
+    pub fn new_centered(Point(x, y): Point, half_width: f64, half_height: f64) -> Bounds {
+        Bounds {
+            min: Point(x - half_width, y - half_height),
+            max: Point(x + half_width, y + half_height),
+        }
+    }
+
+    /// The point halfway between the corners.
+    pub fn center(&self) -> Point {
+        (self.min + self.max) * 0.5
+    }
+
+    /// Half the width and half the height.
+    pub fn half_extents(&self) -> (f64, f64) {
+        (
+            (self.max.0 - self.min.0) / 2.0,
+            (self.max.1 - self.min.1) / 2.0,
+        )
+    }
+
+    /// The bounds grown along one axis, about their centre, to be `width` by `height` in shape.
+    pub fn with_aspect(self, width: usize, height: usize) -> Bounds {
+        let aspect = width as f64 / height as f64;
+        let (half_w, half_h) = self.half_extents();
+        let (half_w, half_h) = (half_w.max(half_h * aspect), half_h.max(half_w / aspect));
+        Bounds::new_centered(self.center(), half_w, half_h)
+    }
+
+    /// The bounds stretched across, about their centre, for pixels `aspect` times as wide as
+    /// they are tall, so that a view framed for square pixels keeps its shape on them.
+    /// `with_pixel_aspect(1.0 / aspect)` undoes it, and 1 leaves the bounds exactly as they were.
+    pub fn with_pixel_aspect(self, aspect: f64) -> Bounds {
+        if aspect == 1.0 {
+            return self;
+        }
+        let (half_w, half_h) = self.half_extents();
+        Bounds::new_centered(self.center(), half_w * aspect, half_h)
+    }
+
+    /// The bounds with the same centre and `zoom` times the magnification, so that they cover
+    /// `1 / zoom` of the width and of the height.
+    pub fn zoomed(self, zoom: f64) -> Bounds {
+        let (half_w, half_h) = self.half_extents();
+        Bounds::new_centered(self.center(), half_w / zoom, half_h / zoom)
+    }
+}
+
+// how many points along each side `autoframe` looks at
+const AUTOFRAME_SAMPLES: usize = 200;
+
+/// The smallest bounds around the points of `search` that stay close for `iterations` steps.
+/// Those take in the whole set, and close it in more tightly the more steps they have to
+/// stay, though too many lose the thin spikes and filaments that escape the coarse grid the
+/// search samples. The bounds are padded by one of its cells all round to take in what fell
+/// between the samples. `None` if nothing stayed close.
+pub fn autoframe<G: Generator + Sync>(
+    generator: &G,
+    search: Bounds,
+    iterations: usize,
+) -> Option<Bounds> {
+    let grid = Grid::new(AUTOFRAME_SAMPLES, AUTOFRAME_SAMPLES, search.min, search.max);
+    let escapes = sample_escapes(&grid, generator, iterations);
+    let slow = grid
+        .0
+        .iter()
+        .flatten()
+        .zip(escapes.0.iter().flatten())
+        .filter(|(_, escape)| escape.count.is_none())
+        .map(|(&p, _)| p);
+    let (min, max) = slow.fold(None, |extent: Option<(Point, Point)>, p| {
+        Some(extent.map_or((p, p), |(Point(x0, y0), Point(x1, y1))| {
+            (
+                Point(x0.min(p.0), y0.min(p.1)),
+                Point(x1.max(p.0), y1.max(p.1)),
+            )
+        }))
+    })?;
+    let (cell_w, cell_h) = Viewport {
+        min: search.min,
+        max: search.max,
+        width: AUTOFRAME_SAMPLES,
+        height: AUTOFRAME_SAMPLES,
+    }
+    .pixel_size();
+    Some(Bounds {
+        min: Point(min.0 - cell_w, min.1 - cell_h),
+        max: Point(max.0 + cell_w, max.1 + cell_h),
+    })
+}
+
+/// Everything needed to reproduce a rendered view, in a form that can be saved and loaded.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct View {
+    pub bounds: Bounds,
+    #[serde(default = "default_palette")]
+    pub palette: String,
+    /// How many degrees the view is turned anticlockwise about its center.
+    #[serde(default)]
+    pub rotation: f64,
+}
+
+impl View {
+    /// A view of `bounds` in the default palette, not turned at all.
+    pub fn new(bounds: Bounds) -> Self {
+        Self {
+            bounds,
+            palette: default_palette(),
+            rotation: 0.0,
+        }
+    }
+
+    /// The same view turned `degrees` anticlockwise about its center.
+    pub fn with_rotation(self, degrees: f64) -> Self {
+        Self {
+            rotation: degrees,
+            ..self
+        }
+    }
+}
+
+fn default_palette() -> String {
+    "OrRd".to_owned()
+}
+
+#[test]
+fn view_rotation_test() {
+    let bounds = Bounds {
+        min: Point(-2.0, -1.0),
+        max: Point(1.0, 1.0),
+    };
+    let json = serde_json::to_string(&View::new(bounds).with_rotation(30.0)).unwrap();
+    let view: View = serde_json::from_str(&json).unwrap();
+    assert_eq!(view.rotation, 30.0);
+    // views saved before they could be turned aren't
+    let view: View =
+        serde_json::from_str(r#"{"bounds": {"min": [-2.0, -1.0], "max": [1.0, 1.0]}}"#).unwrap();
+    assert_eq!(view.rotation, 0.0);
+}
+
+#[test]
+fn viewport_test() {
+    let viewport = Viewport {
+        min: Point(-2.0, -1.0),
+        max: Point(2.0, 1.0),
+        width: 5,
+        height: 3,
+    };
+    let Point(x, y) = viewport.pixel_to_point(4.0, 2.0);
+    assert_eq!((x, y), (2.0, 1.0));
+    let Point(x, y) = viewport.pixel_to_point(1.0, 1.0);
+    assert_eq!((x, y), (-1.0, 0.0));
+    assert_eq!(viewport.point_to_pixel(Point(0.5, -0.5)), (2.5, 0.5));
+}
+
+#[test]
+fn parse_bounds_test() {
+    let bounds: Bounds = "-2.25, -1.5,0.75,1.5".parse().unwrap();
+    assert_eq!((bounds.min.0, bounds.min.1), (-2.25, -1.5));
+    assert_eq!((bounds.max.0, bounds.max.1), (0.75, 1.5));
+    assert_eq!(bounds.to_string(), "-2.25,-1.5,0.75,1.5");
+    let tiny = Bounds {
+        min: Point(-0.1, 1e-300),
+        max: Point(0.1 + 1e-16, 2e-300),
+    };
+    let round_trip: Bounds = tiny.to_string().parse().unwrap();
+    assert_eq!(round_trip.max.0, tiny.max.0);
+    for invalid in &["", "1,2,3", "1,2,3,4,5", "0,0,1,x", "1,0,0,1", "0,0,1,0"] {
+        assert!(invalid.parse::<Bounds>().is_err(), "{}", invalid);
+    }
+}
+
+#[test]
+fn with_aspect_test() {
+    let square = Bounds {
+        min: Point(-1.0, -1.0),
+        max: Point(1.0, 1.0),
+    };
+    let wide = square.with_aspect(3, 2);
+    assert_eq!((wide.min.0, wide.min.1), (-1.5, -1.0));
+    assert_eq!((wide.max.0, wide.max.1), (1.5, 1.0));
+    let tall = square.with_aspect(1, 4);
+    assert_eq!((tall.min.0, tall.min.1), (-1.0, -4.0));
+    assert_eq!((tall.max.0, tall.max.1), (1.0, 4.0));
+}
+
+#[test]
+fn with_pixel_aspect_test() {
+    let bounds = Bounds {
+        min: Point(-2.0, -1.0),
+        max: Point(1.0, 1.0),
+    };
+    // pixels twice as wide as they're tall need twice the width in the same number of them
+    let wide = bounds.with_pixel_aspect(2.0);
+    assert_eq!((wide.min.0, wide.min.1), (-3.5, -1.0));
+    assert_eq!((wide.max.0, wide.max.1), (2.5, 1.0));
+    let back = wide.with_pixel_aspect(0.5);
+    assert_eq!((back.min.0, back.min.1), (-2.0, -1.0));
+    assert_eq!((back.max.0, back.max.1), (1.0, 1.0));
+    let same = Bounds {
+        min: Point(0.1, 0.2),
+        max: Point(0.7, 0.3),
+    }
+    .with_pixel_aspect(1.0);
+    assert_eq!((same.min.0, same.max.0), (0.1, 0.7));
+}
+
+#[test]
+fn new_centered_test() {
+    let bounds = Bounds::new_centered(Point(-0.5, 0.25), 2.0, 1.0);
+    assert_eq!((bounds.min.0, bounds.min.1), (-2.5, -0.75));
+    assert_eq!((bounds.max.0, bounds.max.1), (1.5, 1.25));
+    assert_eq!(bounds.half_extents(), (2.0, 1.0));
+    let Point(x, y) = bounds.center();
+    assert_eq!((x, y), (-0.5, 0.25));
+    let zoomed = bounds.zoomed(4.0);
+    assert_eq!((zoomed.min.0, zoomed.min.1), (-1.0, 0.0));
+    assert_eq!((zoomed.max.0, zoomed.max.1), (0.0, 0.5));
+}
+
+#[test]
+fn autoframe_test() {
+    use crate::mandelbrot::Mandelbrot;
+    let mandelbrot = Mandelbrot::new(Point(0.0, 0.0));
+    let search = Bounds {
+        min: Point(-4.0, -4.0),
+        max: Point(4.0, 4.0),
+    };
+    // the set itself runs from -2 to 0.25 along the real axis and about +-1.12 along the
+    // imaginary one, and a few steps are enough to frame it without much room to spare
+    let framed = autoframe(&mandelbrot, search, 5).unwrap();
+    assert!(framed.min.0 <= -2.0 && framed.min.0 > -2.2, "{}", framed);
+    assert!(framed.max.0 >= 0.25 && framed.max.0 < 0.8, "{}", framed);
+    assert!(framed.min.1 <= -1.12 && framed.min.1 > -1.4, "{}", framed);
+    assert!(framed.max.1 >= 1.12 && framed.max.1 < 1.4, "{}", framed);
+    // while too many lose the spike along the negative real axis
+    assert!(autoframe(&mandelbrot, search, 100).unwrap().min.0 > -1.9);
+
+    let far_away = Bounds {
+        min: Point(10.0, 10.0),
+        max: Point(11.0, 11.0),
+    };
+    assert!(autoframe(&mandelbrot, far_away, 5).is_none());
+}
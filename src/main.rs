@@ -1,89 +1,1595 @@
 use fractals::{
-    char::{CharPalette, CharRenderer},
+    boundary, boxed,
+    burning_ship::BurningShip,
+    char::{CharPalette, CharRenderer, CHAR_DIVERGING},
+    color_escapes,
+    colorize::{self, Scale, Stretched},
     draw,
-    julia::Julia,
+    export::PngRenderer,
+    glynn::Glynn,
+    julia::{self, Julia},
+    lyapunov::{self, Lyapunov},
     mandelbrot::Mandelbrot,
-    rgb::RGBPalette,
-    Grid, Point,
+    power::PowerMandelbrot,
+    registry::{self, Parameters, JULIA_C, MULTIBROT_POWER, NOVA_C, NOVA_RELAXATION},
+    rgb::{
+        color_from_hex, smooth_color_escapes, AdaptiveSampling, Blending, DownsampleRenderer,
+        GlowRenderer, LitRenderer, RGBPalette, RGBRenderer, PALETTE_NAMES,
+    },
+    sample_derivatives, sample_escapes,
+    stats::EscapeStats,
+    trap::{TextureTrap, TrapRenderer},
+    view::{autoframe, Bounds, View, Viewport},
+    DerivativeResult, EscapeResult, Generator, Grid, Palette, Point, Renderer, SamplePattern,
+};
+#[cfg(feature = "deep-zoom")]
+use fractals::{
+    deep::{DeepMandelbrot, PerturbedMandelbrot},
+    precision::DoubleDouble,
 };
 use gui::{zoomable_canvas_render, CanvasState, RGB_HEIGHT, RGB_WIDTH, TERM_HEIGHT, TERM_WIDTH};
-use pixel_canvas::Canvas;
+use pixel_canvas::{Canvas, Color, Image};
+use rand::{rngs::StdRng, SeedableRng};
+use rayon::{prelude::*, ThreadPoolBuilder};
+use serde::Deserialize;
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufReader, ErrorKind},
+    mem::size_of,
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
 use structopt::StructOpt;
+use winit::event::VirtualKeyCode;
 
 mod gui;
+mod text;
 
 #[derive(StructOpt)]
 struct Opt {
     #[structopt(subcommand)]
-    julia: Option<JuliaOpt>,
+    fractal: Option<FractalOpt>,
+
+    // the registry's entry for the subcommand, if it names one
+    #[structopt(skip)]
+    registered: Option<&'static registry::Fractal>,
 
     #[allow(clippy::option_option)]
-    #[structopt(short, long, conflicts_with("julia"))]
+    #[structopt(short, long, conflicts_with("fractal"))]
     mandelbrot: Option<Option<f64>>,
 
     #[allow(dead_code)]
     #[structopt(short, long, conflicts_with("image"))]
     text: bool,
 
+    /// Render the Julia set of a random constant near the edge of the Mandelbrot set
+    #[structopt(long, conflicts_with_all(&["fractal", "mandelbrot"]))]
+    random_julia: bool,
+
+    /// Render the Glynn fractal, the Julia set of z^1.5 - 0.2
+    #[structopt(long, conflicts_with_all(&["fractal", "mandelbrot", "random-julia"]))]
+    glynn: bool,
+
+    /// Render z -> z^N + c for any real N, multiplying out whole powers and using the principal
+    /// branch for the rest
+    #[structopt(
+        long,
+        allow_hyphen_values(true),
+        conflicts_with_all(&["fractal", "mandelbrot", "random-julia", "glynn"])
+    )]
+    power: Option<f64>,
+
+    /// Render one of the named Julia sets: rabbit, basilica, san-marco, dendrite, siegel-disk,
+    /// airplane or cauliflower
+    #[structopt(
+        long,
+        parse(try_from_str = julia::preset),
+        conflicts_with_all(&["fractal", "mandelbrot", "random-julia", "glynn", "power"])
+    )]
+    julia_preset: Option<&'static julia::Preset>,
+
+    /// Render a fractal by the name `list` shows it under, with its usual parameters
+    #[structopt(
+        long = "fractal",
+        name = "fractal",
+        parse(try_from_str = registry::find),
+        conflicts_with_all(&["mandelbrot", "random-julia", "glynn", "power", "julia-preset"])
+    )]
+    fractal_name: Option<&'static registry::Fractal>,
+
+    /// Render each of these fractals, as `list` names them, at the same bounds side by side in
+    /// one --output image, labelled with their names
+    #[structopt(
+        long,
+        require_delimiter(true),
+        parse(try_from_str = registry::find),
+        requires("output"),
+        conflicts_with_all(&[
+            "fractal", "mandelbrot", "random-julia", "glynn", "power", "julia-preset", "both",
+            "autoframe", "dry-run",
+        ])
+    )]
+    compare: Vec<&'static registry::Fractal>,
+
+    /// Seed for --random-julia, to get the same constant again
+    #[structopt(long, requires("random-julia"))]
+    seed: Option<u64>,
+
+    #[structopt(skip)]
+    random_c: Option<Point>,
+
     #[structopt(short, long, conflicts_with("text"))]
     image: bool,
+
+    /// Corners of the starting view as min_re,min_im,max_re,max_im, instead of the fractal's
+    /// usual view
+    #[structopt(long, allow_hyphen_values(true))]
+    bounds: Option<Bounds>,
+
+    /// Center the starting view on this point, e.g. -0.7435+0.1314i, instead of the middle of
+    /// the fractal's usual view
+    #[structopt(long, allow_hyphen_values(true), conflicts_with("bounds"))]
+    center: Option<Point>,
+
+    /// Magnify the starting view this many times over the fractal's usual view, keeping its
+    /// center or --center
+    #[structopt(long, parse(try_from_str = parse_zoom), conflicts_with("bounds"))]
+    zoom: Option<f64>,
+
+    /// Turn the view this many degrees anticlockwise about its center before sampling it
+    #[structopt(long, default_value = "0", allow_hyphen_values(true))]
+    rotate: f64,
+
+    /// How many times wider than tall the pixels of the display are, stretching each view
+    /// across so that its shape comes out right on them
+    #[structopt(long, default_value = "1", parse(try_from_str = parse_pixel_aspect))]
+    pixel_aspect: f64,
+
+    /// Find a view that fits the set closely, within the starting view given by --bounds,
+    /// --center or --zoom, or else twice the usual view, and either print it as --bounds or
+    /// apply it to the render
+    #[structopt(long, possible_values(&["print", "apply"]), conflicts_with("batch"))]
+    autoframe: Option<String>,
+
+    /// The colorbrewer palette to color with, as `list` names it
+    #[structopt(long, default_value = "OrRd", parse(try_from_str = RGBPalette::from_name))]
+    palette: RGBPalette,
+
+    /// How many colors to take from the colorbrewer palette, within the sizes it comes in
+    #[structopt(long)]
+    classes: Option<u32>,
+
+    /// Color for points inside the set, as #rrggbb
+    #[structopt(long, default_value = "#000000", parse(try_from_str = color_from_hex))]
+    interior: Color,
+
+    /// Pick colors by the logarithm of the escape count, which keeps detail near the boundary
+    #[structopt(long)]
+    log_scale: bool,
+
+    /// Raise each iteration's place along the palette to this power before picking its color;
+    /// above 1 spreads the colors over later iterations, below 1 over earlier ones
+    #[structopt(long, default_value = "1.0", parse(try_from_str = parse_exponent))]
+    color_exponent: f64,
+
+    /// Shade --output and --batch images as if the boundary were embossed, lit from this many
+    /// degrees counter-clockwise from the right
+    #[structopt(long, conflicts_with("image"))]
+    light_angle: Option<f64>,
+
+    /// Make --output and --batch images glow around the boundary of the set, fading to a third
+    /// of its brightness this many pixels out
+    #[structopt(long, parse(try_from_str = parse_glow))]
+    glow: Option<f64>,
+
+    /// Color --output and --batch images with this PNG wherever orbits pass close to
+    /// --trap-center, by the pixel under each orbit's closest approach
+    #[structopt(long, parse(from_os_str))]
+    trap_texture: Option<PathBuf>,
+
+    /// Where the middle of the --trap-texture goes in the plane the orbits move in
+    #[structopt(long, default_value = "0", allow_hyphen_values(true))]
+    trap_center: Point,
+
+    /// How wide the --trap-texture is in the plane the orbits move in
+    #[structopt(long, default_value = "1")]
+    trap_size: f64,
+
+    #[structopt(skip)]
+    texture_trap: Option<TextureTrap>,
+
+    /// Print how long each phase of rendering --output, --batch or --stats took to stderr
+    #[structopt(short, long)]
+    verbose: bool,
+
+    #[structopt(skip)]
+    timings: Timings,
+
+    /// Antialias --output and --batch images by rendering N times as wide and as tall and
+    /// averaging each N by N block of pixels, which takes N^2 times as long and as much memory
+    #[structopt(long, default_value = "1", parse(try_from_str = parse_ssaa))]
+    ssaa: usize,
+
+    /// How --ssaa lays out the points of each pixel: grid, rotated or jittered
+    #[structopt(long, default_value = "rotated")]
+    ssaa_pattern: SamplePattern,
+
+    /// Only supersample the pixels of --output and --batch images beside an edge between
+    /// escape counts that differ by more than this, sampling each of them --ssaa by --ssaa
+    /// times in --ssaa-pattern
+    #[structopt(
+        long,
+        conflicts_with_all(&["smooth", "edges", "glow", "light-angle", "trap-texture"])
+    )]
+    adaptive_ssaa: Option<usize>,
+
+    /// Color --output and --batch images by a fractional escape count, so the bands of color
+    /// fade into each other instead of stepping, and start the window that way
+    #[structopt(long)]
+    smooth: bool,
+
+    /// Draw --output and --batch images as black lines on white along the edges between points
+    /// whose escape counts differ by more than this, 0 tracing every band of color
+    #[structopt(long, conflicts_with("smooth"))]
+    edges: Option<usize>,
+
+    /// Blend and shade colors in linear light rather than on their sRGB values, which keeps
+    /// --light-angle shadows from looking muddy
+    #[structopt(long)]
+    linear_light: bool,
+
+    /// Most iterations to follow each point for before treating it as inside the set,
+    /// defaulting to one per palette color
+    #[structopt(long)]
+    max_iter: Option<usize>,
+
+    /// Render each view listed in this JSON file to its own PNG instead of displaying anything
+    #[structopt(long, parse(from_os_str), conflicts_with_all(&["text", "image"]))]
+    batch: Option<PathBuf>,
+
+    /// Print how the starting view's escape counts are spread instead of showing it, to check
+    /// whether --max-iter is high enough, along with how many colors were clamped while drawing
+    /// it as --output would
+    #[structopt(long, conflicts_with_all(&["text", "image", "batch", "output"]))]
+    stats: bool,
+
+    /// Print about how much memory and time drawing the starting view as --output would, or
+    /// every --batch view, takes, timed from a small patch of each, instead of drawing it
+    #[structopt(long, conflicts_with_all(&["text", "image", "stats"]))]
+    dry_run: bool,
+
+    /// Number of threads to render with, defaulting to one per core
+    #[structopt(long, parse(try_from_str = parse_threads))]
+    threads: Option<usize>,
+
+    /// Write a strip of the palette's colors to this PNG instead of rendering a fractal
+    #[structopt(long, parse(from_os_str))]
+    show_palette: Option<PathBuf>,
+
+    /// Render the starting view to this file instead of opening a window; the format comes from
+    /// the extension (.png, or with the image feature, .jpg or .bmp)
+    #[structopt(long, parse(from_os_str), conflicts_with_all(&["text", "image", "batch"]))]
+    output: Option<PathBuf>,
+
+    /// Print the starting view in the terminal as well as saving it with --output
+    #[structopt(long, requires("output"))]
+    both: bool,
+
+    /// JPEG quality from 1 to 100, for --output and --batch
+    #[structopt(long, default_value = "90", parse(try_from_str = parse_quality))]
+    quality: u8,
+
+    /// JSON file of bookmarked views to cycle through with Tab; new bookmarks made with K are saved to it
+    #[structopt(long, parse(from_os_str))]
+    bookmarks: Option<PathBuf>,
+
+    /// How many times the window's width and height the view saved with E is
+    #[structopt(long, default_value = "2", parse(try_from_str = parse_export_scale))]
+    export_scale: usize,
+
+    /// How many finished views the window keeps to show again without recomputing them, at
+    /// about 20MB each; 0 turns this off
+    #[structopt(long, default_value = "4")]
+    cache_size: usize,
 }
 
 #[derive(StructOpt)]
-enum JuliaOpt {
+enum FractalOpt {
     Julia {
-        #[structopt(short, default_value = "0.32")]
-        p: f64,
+        /// The real part of the constant, 0.32 unless given
+        #[structopt(short)]
+        p: Option<f64>,
+
+        /// The imaginary part of the constant, 0.043 unless given
+        #[structopt(short)]
+        z: Option<f64>,
 
-        #[structopt(short, default_value = "0.043")]
-        z: f64,
+        /// The constant as one complex number, e.g. 0.32+0.043i, instead of -p and -z
+        #[structopt(short, long, allow_hyphen_values(true), conflicts_with_all(&["p", "z"]))]
+        c: Option<Point>,
     },
+    /// The Burning Ship fractal
+    BurningShip,
+    /// The Collatz map extended to the complex plane
+    Collatz,
+    /// The Mandelbrot set with the real part of each step folded to its absolute value
+    Celtic,
+    /// The Mandelbrot set raised to any real power, z -> z^e + c
+    Multibrot {
+        /// The power e, 3 unless given
+        #[structopt(long)]
+        exponent: Option<f64>,
+    },
+    /// The Mandelbrot set with each step squaring |Re z| - i Im z
+    Perpendicular,
+    /// Newton's method for z^3 - 1 with relaxation and an added constant c = p + zi
+    Nova {
+        /// How far along each Newton step to move, 1 unless given
+        #[structopt(long)]
+        relaxation: Option<f64>,
+
+        /// The real part of c, -0.3 unless given
+        #[structopt(short)]
+        p: Option<f64>,
+
+        /// The imaginary part of c, 0 unless given
+        #[structopt(short)]
+        z: Option<f64>,
+    },
+    /// The exponential map z -> c exp(z)
+    Exponential,
+    /// The Magnet Type I fractal, z -> ((z^2 + c - 1) / (2z + c - 2))^2
+    Magnet,
+    /// The spider fractal, z -> z^2 + c with c -> c/2 + z moving along each step
+    Spider,
+    /// The Mandelbrot set around a center given to about 32 digits, for zooming in past 1e-13
+    #[cfg(feature = "deep-zoom")]
+    Deep {
+        /// Real part of the center
+        #[structopt(
+            long,
+            default_value = "-0.743643887037158704752191506114774",
+            allow_hyphen_values(true)
+        )]
+        re: DoubleDouble,
+
+        /// Imaginary part of the center
+        #[structopt(
+            long,
+            default_value = "0.131825904205311970493132056385139",
+            allow_hyphen_values(true)
+        )]
+        im: DoubleDouble,
+
+        /// Half the height of the starting view
+        #[structopt(long, default_value = "1e-15")]
+        radius: f64,
+
+        /// Iterate only the center in full precision and every other point by its drift from it
+        #[structopt(long)]
+        perturbation: bool,
+    },
+    /// Lyapunov exponents of the logistic map, with growth rates alternating between x and y
+    Lyapunov {
+        /// The order in which the growth rates x (A) and y (B) are applied, e.g. AABAB
+        #[structopt(long, default_value = "AB")]
+        sequence: String,
+    },
+    /// Print the fractals and palettes there are to choose from
+    List,
 }
 
-fn main() {
-    let opt = Opt::from_args();
-    if opt.image {
-        let canvas = Canvas::new(RGB_WIDTH, RGB_HEIGHT);
-        if let Some(JuliaOpt::Julia { z, p }) = opt.julia {
-            canvas
-                .title("Julia")
-                .state(CanvasState::new(Point(-1.5, -1.5), Point(1.5, 1.5)))
-                .input(CanvasState::handle_input)
-                .render(zoomable_canvas_render(
-                    Julia::new(Point(p, z)),
-                    RGBPalette::new(),
-                ))
+impl Opt {
+    fn mandelbrot_z(&self) -> f64 {
+        self.mandelbrot.unwrap_or(Some(0.0)).unwrap_or(0.0)
+    }
+
+    fn julia_c(&self) -> Point {
+        if let Some(c) = self.random_c {
+            return c;
+        }
+        if let Some(preset) = self.julia_preset {
+            return preset.c;
+        }
+        match self.fractal {
+            Some(FractalOpt::Julia { c: Some(c), .. }) => c,
+            Some(FractalOpt::Julia { p, z, .. }) => {
+                Point(p.unwrap_or(JULIA_C.0), z.unwrap_or(JULIA_C.1))
+            }
+            _ => JULIA_C,
+        }
+    }
+
+    fn scale(&self) -> Scale {
+        if self.log_scale {
+            Scale::Logarithmic
         } else {
-            let z = opt.mandelbrot.unwrap_or(Some(0.0)).unwrap_or(0.0);
-            canvas
-                .title("Mandelbrot")
-                .state(CanvasState::new(Point(-2.25, -1.5), Point(0.75, 1.5)))
-                .input(CanvasState::handle_input)
-                .render(zoomable_canvas_render(
-                    Mandelbrot::new(Point(z, z)),
-                    RGBPalette::new(),
-                ))
+            Scale::Linear
         }
-    } else {
-        let palette = CharPalette;
-        let mut renderer = CharRenderer;
-        if let Some(JuliaOpt::Julia { z, p }) = opt.julia {
-            let grid = Grid::new(TERM_WIDTH, TERM_HEIGHT, Point(-1.5, -1.5), Point(1.5, 1.5));
-            draw(&Julia::new(Point(p, z)), &palette, &mut renderer, &grid);
+    }
+
+    // a linear scale keeps one color per iteration, a logarithmic one needs more
+    // iterations than colors to make a difference
+    fn stretch<P: Palette>(&self, palette: &P) -> Stretched<P::Item> {
+        let iterations = match self.scale() {
+            Scale::Linear => palette.get().count() as u32,
+            Scale::Logarithmic => self.max_iter.map_or(LOG_SCALE_ITERATIONS, |n| n as u32),
+        };
+        Stretched::new(palette, self.scale(), iterations, self.color_exponent)
+    }
+
+    // a colorbrewer palette with the requested number of classes and interior, stretched
+    fn rgb_palette(&self, palette: RGBPalette) -> Result<Stretched<Color>, String> {
+        let palette = match self.classes {
+            Some(classes) => palette.with_classes(classes)?,
+            None => palette,
+        };
+        Ok(self.stretch(&palette.with_interior(self.interior)))
+    }
+
+    /// The flags that pick the palette and iterations, as given to the window showing them, so
+    /// that a printed command draws the same colors.
+    fn shared_args(&self, max_iter: usize) -> Vec<String> {
+        let program = std::env::args().next();
+        let mut args = vec![program.unwrap_or_else(|| "fractals".to_owned())];
+        args.push("--image".to_owned());
+        args.push(format!("--max-iter={}", max_iter));
+        if let Some(classes) = self.classes {
+            args.push(format!("--classes={}", classes));
+        }
+        let Color { r, g, b } = self.interior;
+        if (r, g, b) != (0, 0, 0) {
+            args.push(format!("--interior=#{:02x}{:02x}{:02x}", r, g, b));
+        }
+        if self.log_scale {
+            args.push("--log-scale".to_owned());
+        }
+        if self.color_exponent != 1.0 {
+            args.push(format!("--color-exponent={}", self.color_exponent));
+        }
+        if self.pixel_aspect != 1.0 {
+            args.push(format!("--pixel-aspect={}", self.pixel_aspect));
+        }
+        args
+    }
+
+    /// The arguments that pick the fractal being drawn, with any random constant filled in.
+    fn fractal_args(&self) -> Vec<String> {
+        if let Some(fractal) = self.fractal_name {
+            vec![format!("--fractal={}", fractal.name)]
+        } else if self.glynn {
+            vec!["--glynn".to_owned()]
+        } else if let Some(power) = self.power {
+            vec![format!("--power={}", power)]
+        } else if let Some(preset) = self.julia_preset {
+            vec![format!("--julia-preset={}", preset.name)]
+        } else if self.random_c.is_some() {
+            vec!["julia".to_owned(), format!("--c={}", self.julia_c())]
         } else {
-            let z = opt.mandelbrot.unwrap_or(Some(0.0)).unwrap_or(0.0);
-            let grid = Grid::new(
-                TERM_WIDTH,
-                TERM_HEIGHT,
-                Point(-2.25, -1.5),
-                Point(0.75, 1.5),
-            );
-            draw(
-                &Mandelbrot::new(Point(z, z)),
-                &palette,
+            match &self.fractal {
+                None => match self.mandelbrot {
+                    Some(Some(z)) => vec![format!("--mandelbrot={}", z)],
+                    _ => vec![],
+                },
+                Some(FractalOpt::Julia { .. }) => {
+                    vec!["julia".to_owned(), format!("--c={}", self.julia_c())]
+                }
+                Some(FractalOpt::Multibrot { exponent }) => vec![
+                    "multibrot".to_owned(),
+                    format!("--exponent={}", exponent.unwrap_or(MULTIBROT_POWER)),
+                ],
+                Some(FractalOpt::Nova { relaxation, p, z }) => vec![
+                    "nova".to_owned(),
+                    format!("--relaxation={}", relaxation.unwrap_or(NOVA_RELAXATION)),
+                    format!("-p={}", p.unwrap_or(NOVA_C.0)),
+                    format!("-z={}", z.unwrap_or(NOVA_C.1)),
+                ],
+                #[cfg(feature = "deep-zoom")]
+                Some(FractalOpt::Deep {
+                    re,
+                    im,
+                    radius,
+                    perturbation,
+                }) => {
+                    let mut args = vec![
+                        "deep".to_owned(),
+                        format!("--re={}", re),
+                        format!("--im={}", im),
+                        format!("--radius={}", radius),
+                    ];
+                    if *perturbation {
+                        args.push("--perturbation".to_owned());
+                    }
+                    args
+                }
+                Some(FractalOpt::Lyapunov { sequence }) => {
+                    vec!["lyapunov".to_owned(), format!("--sequence={}", sequence)]
+                }
+                Some(FractalOpt::List) => vec!["list".to_owned()],
+                // the rest take no parameters, so their name is all there is to them
+                Some(_) => self.registered.iter().map(|f| f.name.to_owned()).collect(),
+            }
+        }
+    }
+
+    fn blending(&self) -> Blending {
+        if self.linear_light {
+            Blending::Linear
+        } else {
+            Blending::Gamma
+        }
+    }
+
+    // the view --bounds, --center or --zoom start at, if they were given, with `usual` as the
+    // one --center and --zoom move and magnify
+    fn starting_bounds(&self, usual: Bounds) -> Option<Bounds> {
+        if self.bounds.is_some() {
+            return self.bounds;
+        }
+        if self.center.is_none() && self.zoom.is_none() {
+            return None;
+        }
+        let (half_w, half_h) = usual.half_extents();
+        let zoomed = Bounds::new_centered(self.center.unwrap_or(usual.center()), half_w, half_h)
+            .zoomed(self.zoom.unwrap_or(1.0));
+        Some(zoomed)
+    }
+
+    // `grid`, sampled between `min` and `max`, turned about their middle by --rotate
+    fn rotated(&self, grid: Grid<Point>, min: Point, max: Point) -> Grid<Point> {
+        grid.rotated_about(Bounds { min, max }.center(), self.rotate.to_radians())
+    }
+
+    // the power z is raised to each step, which --smooth needs to make the bands meet
+    fn power_of_z(&self) -> f64 {
+        match (self.power, self.fractal_name.or(self.registered)) {
+            (Some(power), _) => power,
+            (None, Some(fractal)) => self.parameters().power.unwrap_or(fractal.power),
+            (None, None) => 2.0,
+        }
+    }
+
+    // what the subcommand says to make the registry's generator with
+    fn parameters(&self) -> Parameters {
+        match self.fractal {
+            Some(FractalOpt::Julia { .. }) => Parameters {
+                c: Some(self.julia_c()),
+                ..Parameters::default()
+            },
+            Some(FractalOpt::Multibrot { exponent }) => Parameters {
+                power: exponent,
+                ..Parameters::default()
+            },
+            Some(FractalOpt::Nova { relaxation, p, z }) => Parameters {
+                c: Some(Point(p.unwrap_or(NOVA_C.0), z.unwrap_or(NOVA_C.1))),
+                relaxation,
+                ..Parameters::default()
+            },
+            _ => Parameters::default(),
+        }
+    }
+
+    // runs `f`, adding the time it took to `phase` if --verbose was given
+    fn time<T>(&self, phase: &'static str, f: impl FnOnce() -> T) -> T {
+        if !self.verbose {
+            return f();
+        }
+        let start = Instant::now();
+        let result = f();
+        self.timings.add(phase, start.elapsed());
+        result
+    }
+
+    // the palette's colors get scaled over however many iterations are followed, so this
+    // only decides the shape, but the default keeps one color per iteration
+    fn max_iter<P: Palette>(&self, palette: &P) -> usize {
+        self.max_iter.unwrap_or_else(|| palette.get().count())
+    }
+}
+
+// how many steps points have to stay close for --autoframe to count them as part of the set,
+// which is few enough to keep the thin spikes of the Mandelbrot set in
+const AUTOFRAME_ITERATIONS: usize = 5;
+
+// The views of the fractals the registry has no generator for, as the Lyapunov fractal isn't
+// drawn with one and --power needs its power first. The deep zoom is left out too, as its view
+// depends on the radius given.
+const OTHER_VIEWS: &[(&str, Point, Point)] = &[
+    ("lyapunov", Point(2.0, 2.0), Point(4.0, 4.0)),
+    ("--power", Point(-1.875, -1.25), Point(1.875, 1.25)),
+];
+
+fn default_view(name: &str) -> (Point, Point) {
+    if let Ok(fractal) = registry::find(name) {
+        return (fractal.min, fractal.max);
+    }
+    OTHER_VIEWS
+        .iter()
+        .find(|(fractal, ..)| *fractal == name)
+        .map(|&(_, min, max)| (min, max))
+        .unwrap_or_else(|| panic!("no default view for {}", name))
+}
+
+fn print_list() {
+    println!("fractals, with the view each starts at:");
+    for fractal in registry::FRACTALS {
+        let bounds = Bounds {
+            min: fractal.min,
+            max: fractal.max,
+        };
+        println!("  {:<16} {}", fractal.name, bounds);
+    }
+    for &(name, min, max) in OTHER_VIEWS {
+        println!("  {:<16} {}", name, Bounds { min, max });
+    }
+    #[cfg(feature = "deep-zoom")]
+    println!(
+        "  {:<16} centred on --re and --im, twice --radius tall",
+        "deep"
+    );
+    let presets: Vec<_> = julia::PRESETS.iter().map(|preset| preset.name).collect();
+    println!("Julia presets: {}", presets.join(", "));
+    println!("palettes: {}", PALETTE_NAMES.join(", "));
+}
+
+// How long each phase of rendering took altogether, in the order they first ran. Batch views
+// render at the same time, so their phases can add up to more than the whole batch took.
+#[derive(Default)]
+struct Timings(Mutex<Vec<(&'static str, Duration)>>);
+
+impl Timings {
+    fn add(&self, phase: &'static str, elapsed: Duration) {
+        let mut phases = self.0.lock().unwrap();
+        match phases.iter_mut().find(|(name, _)| *name == phase) {
+            Some((_, total)) => *total += elapsed,
+            None => phases.push((phase, elapsed)),
+        }
+    }
+}
+
+impl fmt::Display for Timings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let phases = self.0.lock().unwrap();
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        writeln!(f, "{:<10} {:>10}", "phase", "ms")?;
+        for &(phase, elapsed) in phases.iter() {
+            writeln!(f, "{:<10} {:>10.1}", phase, ms(elapsed))?;
+        }
+        let total = phases.iter().map(|&(_, elapsed)| elapsed).sum();
+        writeln!(f, "{:<10} {:>10.1}", "total", ms(total))
+    }
+}
+
+const LOG_SCALE_ITERATIONS: u32 = 256;
+
+// how many steps of the deep zoom's reference orbit to compute in full precision when
+// --max-iter isn't given. Points that outlast it carry on in full precision themselves, so
+// this only trades the time spent up front against the time spent on them.
+#[cfg(feature = "deep-zoom")]
+const DEEP_REFERENCE_ITERATIONS: usize = 256;
+
+// how many ranges of escape counts --stats sorts points into
+const STATS_BUCKETS: usize = 10;
+
+fn parse_exponent(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(exponent) if exponent > 0.0 && exponent.is_finite() => Ok(exponent),
+        Ok(_) => Err("the exponent must be a positive number".to_owned()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn parse_glow(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(width) if width > 0.0 && width.is_finite() => Ok(width),
+        Ok(_) => Err("the glow width must be a positive number of pixels".to_owned()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn parse_zoom(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(zoom) if zoom > 0.0 && zoom.is_finite() => Ok(zoom),
+        Ok(_) => Err("the zoom must be a positive number".to_owned()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn parse_pixel_aspect(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(aspect) if aspect > 0.0 && aspect.is_finite() => Ok(aspect),
+        Ok(_) => Err("the pixel aspect must be a positive number".to_owned()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn parse_ssaa(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(0) => Err("the supersampling factor must be at least 1".to_owned()),
+        Ok(factor) => Ok(factor),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn parse_quality(s: &str) -> Result<u8, String> {
+    match s.parse::<u8>() {
+        Ok(quality) if (1..=100).contains(&quality) => Ok(quality),
+        Ok(_) => Err("the JPEG quality must be from 1 to 100".to_owned()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn parse_threads(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(0) => Err("there must be at least 1 thread".to_owned()),
+        Ok(threads) => Ok(threads),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn parse_export_scale(s: &str) -> Result<usize, String> {
+    match s.parse::<usize>() {
+        Ok(0) => Err("the export scale must be at least 1".to_owned()),
+        Ok(scale) => Ok(scale),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+struct BatchEntry {
+    #[serde(flatten)]
+    view: View,
+    output: PathBuf,
+}
+
+// How the orbits of a view drawn for saving ended, one for each point sampled, and how many of
+// its colors had a channel clamped on the way, which --stats reports
+struct Drawn {
+    escapes: Grid<EscapeResult>,
+    clipped: usize,
+}
+
+// draws a view for saving, supersampling it if asked, and adding any glow on top of the
+// shading if a light angle was given. `power` is what the fractal raises z to, for --smooth.
+fn draw_file<G: Generator + Sync>(
+    generator: &G,
+    power: f64,
+    opt: &Opt,
+    palette: &Stretched<Color>,
+    min: Point,
+    max: Point,
+) -> io::Result<(PngRenderer, Drawn)> {
+    let max_iter = opt.max_iter(palette);
+    if let Some(threshold) = opt.adaptive_ssaa {
+        return draw_adaptive(generator, opt, palette, min, max, threshold);
+    }
+    let grid = opt.time("grid", || {
+        let grid = Grid::supersampled_in_pattern(
+            RGB_WIDTH,
+            RGB_HEIGHT,
+            min,
+            max,
+            opt.ssaa,
+            opt.ssaa_pattern,
+        );
+        opt.rotated(grid, min, max)
+    });
+    let sampled = sample_view(generator, opt, &grid, max_iter)?;
+    let renderer = PngRenderer::new().with_quality(opt.quality);
+    let renderer = DownsampleRenderer::new(renderer, opt.ssaa).with_blending(opt.blending());
+    let renderer = match opt.glow {
+        Some(pixels) => {
+            let width = pixels * (max.0 - min.0) / (RGB_WIDTH - 1) as f64;
+            let strength = opt.time("shading", || sampled.shading(|result| result.glow(width)));
+            let renderer = GlowRenderer::new(renderer, strength).with_blending(opt.blending());
+            let (renderer, drawn) =
+                draw_trapped(generator, power, opt, palette, &grid, sampled, renderer)?;
+            (renderer.into_inner(), drawn)
+        }
+        None => draw_trapped(generator, power, opt, palette, &grid, sampled, renderer)?,
+    };
+    Ok((renderer.0.into_inner(), renderer.1))
+}
+
+// draws a view for saving with only its edges supersampled, which --adaptive-ssaa keeps to
+// plain colors
+fn draw_adaptive<G: Generator + Sync>(
+    generator: &G,
+    opt: &Opt,
+    palette: &Stretched<Color>,
+    min: Point,
+    max: Point,
+    threshold: usize,
+) -> io::Result<(PngRenderer, Drawn)> {
+    let max_iter = opt.max_iter(palette);
+    let grid = opt.time("grid", || {
+        opt.rotated(Grid::new(RGB_WIDTH, RGB_HEIGHT, min, max), min, max)
+    });
+    let escapes = opt.time("sampling", || sample_escapes(&grid, generator, max_iter));
+    let viewport = Viewport {
+        min,
+        max,
+        width: RGB_WIDTH,
+        height: RGB_HEIGHT,
+    };
+    let sampling = AdaptiveSampling::new(threshold, opt.ssaa)
+        .with_pattern(opt.ssaa_pattern)
+        .with_blending(opt.blending())
+        .with_rotation(Bounds { min, max }.center(), opt.rotate.to_radians());
+    let (colors, clipped) = opt.time("resampling", || {
+        sampling.color_escapes(generator, palette, max_iter, &escapes, viewport)
+    });
+    let mut renderer = PngRenderer::new().with_quality(opt.quality);
+    opt.time("rendering", || {
+        renderer.render(colors);
+        renderer.finish()
+    })?;
+    Ok((renderer, Drawn { escapes, clipped }))
+}
+
+// how the orbits of a view ended, followed once for coloring it and for any shading of it
+struct Sampled {
+    escapes: Grid<EscapeResult>,
+    // with the derivative of where each one ended, when --light-angle or --glow shade by it
+    derivatives: Option<Grid<DerivativeResult>>,
+}
+
+impl Sampled {
+    // how `shade` shades each point of the view
+    fn shading(&self, shade: impl Fn(&DerivativeResult) -> f64 + Sync) -> Grid<f64> {
+        let derivatives = self.derivatives.as_ref();
+        derivatives
+            .expect("shaded views keep their derivatives")
+            .par_map_ref(shade)
+    }
+}
+
+// follows the orbits of the points of `grid`, keeping their derivatives as well if
+// --light-angle or --glow are going to shade the view with them
+fn sample_view<G: Generator + Sync>(
+    generator: &G,
+    opt: &Opt,
+    grid: &Grid<Point>,
+    max_iter: usize,
+) -> io::Result<Sampled> {
+    if opt.light_angle.is_none() && opt.glow.is_none() {
+        let escapes = opt.time("sampling", || sample_escapes(grid, generator, max_iter));
+        return Ok(Sampled {
+            escapes,
+            derivatives: None,
+        });
+    }
+    if generator
+        .generate_with_derivative(Point(0.0, 0.0))
+        .is_none()
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "--light-angle and --glow need a fractal whose orbits have a derivative",
+        ));
+    }
+    let derivatives = opt.time("sampling", || sample_derivatives(grid, generator, max_iter));
+    Ok(Sampled {
+        escapes: derivatives.par_map_ref(|result| result.escape),
+        derivatives: Some(derivatives),
+    })
+}
+
+// draws a view through `renderer` like `draw_lit`, with the texture trap over it if there is one
+fn draw_trapped<G, R>(
+    generator: &G,
+    power: f64,
+    opt: &Opt,
+    palette: &Stretched<Color>,
+    grid: &Grid<Point>,
+    sampled: Sampled,
+    renderer: R,
+) -> io::Result<(R, Drawn)>
+where
+    G: Generator + Sync,
+    R: Renderer<Item = Color>,
+{
+    match &opt.texture_trap {
+        Some(trap) => {
+            let max_iter = opt.max_iter(palette);
+            let texels = opt.time("shading", || {
+                grid.par_map_ref(|&p| trap.texel(generator, p, max_iter))
+            });
+            let renderer = TrapRenderer::new(renderer, texels);
+            let (renderer, drawn) = draw_lit(generator, power, opt, palette, sampled, renderer)?;
+            Ok((renderer.into_inner(), drawn))
+        }
+        None => draw_lit(generator, power, opt, palette, sampled, renderer),
+    }
+}
+
+// draws a view through `renderer`, shading it first if a light angle was given
+fn draw_lit<G, R>(
+    generator: &G,
+    power: f64,
+    opt: &Opt,
+    palette: &Stretched<Color>,
+    sampled: Sampled,
+    renderer: R,
+) -> io::Result<(R, Drawn)>
+where
+    G: Generator + Sync,
+    R: Renderer<Item = Color>,
+{
+    match opt.light_angle {
+        Some(degrees) => {
+            let angle = degrees.to_radians();
+            let light = opt.time("shading", || sampled.shading(|result| result.light(angle)));
+            let mut renderer = LitRenderer::new(renderer, light).with_blending(opt.blending());
+            let drawn = paint(
+                generator,
+                power,
+                opt,
+                palette,
+                sampled.escapes,
                 &mut renderer,
-                &grid,
-            );
+            )?;
+            Ok((renderer.into_inner(), drawn))
+        }
+        None => {
+            let mut renderer = renderer;
+            let drawn = paint(
+                generator,
+                power,
+                opt,
+                palette,
+                sampled.escapes,
+                &mut renderer,
+            )?;
+            Ok((renderer, drawn))
+        }
+    }
+}
+
+fn paint<G, R>(
+    generator: &G,
+    power: f64,
+    opt: &Opt,
+    palette: &Stretched<Color>,
+    escapes: Grid<EscapeResult>,
+    renderer: &mut R,
+) -> io::Result<Drawn>
+where
+    G: Generator + Sync,
+    R: Renderer<Item = Color>,
+{
+    let max_iter = opt.max_iter(palette);
+    let (colors, clipped) = opt.time("coloring", || {
+        if let Some(threshold) = opt.edges {
+            let edges = boundary(&escapes, max_iter, threshold).par_map_ref(|&edge| {
+                if edge {
+                    Color::BLACK
+                } else {
+                    Color::WHITE
+                }
+            });
+            (edges, 0)
+        } else if opt.smooth {
+            smooth_color_escapes(
+                &escapes,
+                palette,
+                max_iter,
+                power,
+                generator.bailout(),
+                opt.blending(),
+            )
+        } else {
+            (color_escapes(&escapes, palette, max_iter, 0), 0)
+        }
+    });
+    opt.time("rendering", || {
+        renderer.render(colors);
+        renderer.finish()
+    })?;
+    Ok(Drawn {
+        escapes,
+        clipped: clipped + renderer.clipped(),
+    })
+}
+
+fn render_view<G: Generator + Sync>(
+    generator: &G,
+    opt: &Opt,
+    entry: &BatchEntry,
+) -> Result<(), String> {
+    let View {
+        bounds,
+        palette,
+        rotation,
+    } = &entry.view;
+    if bounds.min.0 >= bounds.max.0 || bounds.min.1 >= bounds.max.1 {
+        return Err(format!("bounds {:?} don't cover any area", bounds));
+    }
+    if *rotation != 0.0 {
+        return Err(
+            "batch views are all turned by --rotate, not each by its own rotation".to_owned(),
+        );
+    }
+    let palette = opt.time("palette", || {
+        opt.rgb_palette(RGBPalette::from_name(palette)?)
+    })?;
+    let Bounds { min, max } = bounds.with_pixel_aspect(opt.pixel_aspect);
+    let (png, _) = draw_file(generator, opt.power_of_z(), opt, &palette, min, max)
+        .map_err(|e| e.to_string())?;
+    opt.time("encoding", || png.save(&entry.output))
+        .map_err(|e| e.to_string())
+}
+
+fn load_batch(path: &Path) -> Result<Vec<BatchEntry>, String> {
+    let file = File::open(path).map_err(|e| format!("couldn't open {}: {}", path.display(), e))?;
+    serde_json::from_reader(BufReader::new(file))
+        .map_err(|e| format!("couldn't read {}: {}", path.display(), e))
+}
+
+fn render_batch<G: Generator + Sync>(generator: &G, opt: &Opt, path: &Path) -> Result<(), String> {
+    let entries = load_batch(path)?;
+
+    let total = entries.len();
+    let finished = AtomicUsize::new(0);
+    // views render in parallel, and so do the rows within each, all in rayon's one pool, so
+    // idle threads steal rows from whichever views are left rather than adding more threads
+    let failures = entries
+        .par_iter()
+        .filter(|entry| {
+            let result = render_view(generator, opt, entry);
+            let count = finished.fetch_add(1, Ordering::SeqCst) + 1;
+            match &result {
+                Ok(()) => eprintln!("[{}/{}] rendered {}", count, total, entry.output.display()),
+                Err(e) => eprintln!(
+                    "[{}/{}] failed to render {}: {}",
+                    count,
+                    total,
+                    entry.output.display(),
+                    e
+                ),
+            }
+            result.is_err()
+        })
+        .count();
+
+    if failures == 0 {
+        Ok(())
+    } else {
+        Err(format!("{} of {} views failed to render", failures, total))
+    }
+}
+
+// how many points across the patch --dry-run times, spread over the whole view so that both
+// the slow parts near the set and the quick ones far from it count
+const DRY_RUN_PATCH: usize = 64;
+
+// About how many bytes drawing a view for saving holds at once: the points sampled, their
+// escapes and colors, any shading, and the image they're averaged down to.
+fn view_bytes(opt: &Opt) -> usize {
+    let samples = RGB_WIDTH * RGB_HEIGHT * sampling_factor(opt).pow(2);
+    let mut per_sample = size_of::<Point>() + size_of::<EscapeResult>() + size_of::<Color>();
+    if opt.glow.is_some() {
+        per_sample += size_of::<f64>();
+    }
+    if opt.light_angle.is_some() {
+        per_sample += size_of::<f64>();
+    }
+    if opt.texture_trap.is_some() {
+        per_sample += size_of::<Option<Color>>();
+    }
+    samples * per_sample + RGB_WIDTH * RGB_HEIGHT * size_of::<Color>()
+}
+
+// how many samples across each pixel the whole view is drawn with, as --adaptive-ssaa only
+// supersamples the edges afterwards
+fn sampling_factor(opt: &Opt) -> usize {
+    if opt.adaptive_ssaa.is_some() {
+        1
+    } else {
+        opt.ssaa
+    }
+}
+
+// Times drawing a patch of the view the way draw_file would and scales it up to every sample
+// of the view.
+fn estimate_time<G: Generator + Sync>(
+    generator: &G,
+    opt: &Opt,
+    palette: &Stretched<Color>,
+    min: Point,
+    max: Point,
+) -> Result<Duration, String> {
+    let (width, height) = (DRY_RUN_PATCH, DRY_RUN_PATCH * RGB_HEIGHT / RGB_WIDTH);
+    let patch = Grid::new(width, height, min, max);
+    let start = Instant::now();
+    let sampled =
+        sample_view(generator, opt, &patch, opt.max_iter(palette)).map_err(|e| e.to_string())?;
+    if let Some(pixels) = opt.glow {
+        let width = pixels * (max.0 - min.0) / (RGB_WIDTH - 1) as f64;
+        sampled.shading(|result| result.glow(width));
+    }
+    draw_trapped(
+        generator,
+        opt.power_of_z(),
+        opt,
+        palette,
+        &patch,
+        sampled,
+        PngRenderer::new(),
+    )
+    .map_err(|e| e.to_string())?;
+    let samples = RGB_WIDTH * RGB_HEIGHT * sampling_factor(opt).pow(2);
+    Ok(start
+        .elapsed()
+        .mul_f64(samples as f64 / (width * height) as f64))
+}
+
+fn dry_run<G: Generator + Sync>(
+    generator: &G,
+    opt: &Opt,
+    palette: impl Fn() -> Stretched<Color>,
+    min: Point,
+    max: Point,
+) -> Result<(), String> {
+    let factor = sampling_factor(opt);
+    let (views, time) = match &opt.batch {
+        Some(path) => {
+            let mut time = Duration::ZERO;
+            let entries = load_batch(path)?;
+            for BatchEntry { view, .. } in &entries {
+                let palette = opt.rgb_palette(RGBPalette::from_name(&view.palette)?)?;
+                let Bounds { min, max } = view.bounds.with_pixel_aspect(opt.pixel_aspect);
+                time += estimate_time(generator, opt, &palette, min, max)?;
+            }
+            (entries.len(), time)
+        }
+        None => (1, estimate_time(generator, opt, &palette(), min, max)?),
+    };
+    // batch views render side by side, as many at once as there are threads
+    let at_once = views.min(rayon::current_num_threads());
+    let megabytes = (view_bytes(opt) * at_once) as f64 / 1e6;
+    println!(
+        "{} {} of {}x{} pixels with {}x{} samples each",
+        views,
+        if views == 1 { "view" } else { "views" },
+        RGB_WIDTH,
+        RGB_HEIGHT,
+        factor,
+        factor
+    );
+    println!("memory: about {:.1} MB", megabytes);
+    print!("time: about {:.1?}", time);
+    if opt.adaptive_ssaa.is_some() {
+        print!(", and longer for the edges --adaptive-ssaa supersamples");
+    }
+    println!();
+    Ok(())
+}
+
+// how far each --compare label sits from the top left corner of its panel
+const COMPARE_LABEL_MARGIN: usize = 8;
+
+// draws each fractal at the same view side by side, from the first one's usual view unless
+// another is given, and saves them to `output` as one image
+fn save_comparison(
+    opt: &Opt,
+    fractals: &[&'static registry::Fractal],
+    output: &Path,
+) -> Result<(), String> {
+    let usual = Bounds {
+        min: fractals[0].min,
+        max: fractals[0].max,
+    };
+    let Bounds { min, max } = opt
+        .starting_bounds(usual)
+        .unwrap_or(usual)
+        .with_pixel_aspect(opt.pixel_aspect);
+    let palette = opt.time("palette", || opt.rgb_palette(opt.palette.clone()))?;
+    let mut image = Image::new(RGB_WIDTH * fractals.len(), RGB_HEIGHT);
+    for (i, fractal) in fractals.iter().enumerate() {
+        let (panel, _) = draw_file(
+            &(fractal.generator)(&Parameters::default()),
+            fractal.power,
+            opt,
+            &palette,
+            min,
+            max,
+        )
+        .map_err(|e| e.to_string())?;
+        let left = i * RGB_WIDTH;
+        RGBRenderer::at(&mut image, left, 0).render(panel.into_grid().unwrap());
+        let label_height = text::label_size(fractal.title).1;
+        let top = RGB_HEIGHT - COMPARE_LABEL_MARGIN - label_height;
+        text::draw_label(&mut image, left + COMPARE_LABEL_MARGIN, top, fractal.title);
+    }
+    let rows = image.chunks(image.width()).map(<[Color]>::to_vec).collect();
+    let mut png = PngRenderer::new().with_quality(opt.quality);
+    png.render(Grid(rows));
+    opt.time("encoding", || png.save(output))
+        .map_err(|e| format!("couldn't save {}: {}", output.display(), e))
+}
+
+const SWATCH_HEIGHT: usize = 64;
+
+fn save_swatch(opt: &Opt, path: &Path) -> Result<(), String> {
+    let palette = opt.rgb_palette(opt.palette.clone())?;
+    let width = (RGB_WIDTH / palette.get().count()).max(1);
+    let mut renderer = PngRenderer::new();
+    renderer.render(colorize::swatch(&palette, width, SWATCH_HEIGHT));
+    renderer
+        .save(path)
+        .map_err(|e| format!("couldn't save {}: {}", path.display(), e))
+}
+
+fn load_bookmarks(path: &Path) -> Result<Vec<View>, String> {
+    match File::open(path) {
+        Ok(file) => serde_json::from_reader(BufReader::new(file))
+            .map_err(|e| format!("couldn't read bookmarks from {}: {}", path.display(), e)),
+        // nothing has been bookmarked yet
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(vec![]),
+        Err(e) => Err(format!("couldn't open {}: {}", path.display(), e)),
+    }
+}
+
+// runs the fractal registered as `name`, under its title and from its usual view, with a
+// generator made from the parameters given
+fn run_as<G: Generator + Send + Sync + 'static>(
+    opt: &Opt,
+    fractal: &'static registry::Fractal,
+    generator: G,
+) {
+    let julia_preview = fractal.name == registry::MANDELBROT.name;
+    run(
+        opt,
+        fractal.title,
+        generator,
+        (fractal.min, fractal.max),
+        julia_preview,
+    )
+}
+
+fn run<G: Generator + Send + Sync + 'static>(
+    opt: &Opt,
+    title: &str,
+    generator: G,
+    (min, max): (Point, Point),
+    julia_preview: bool,
+) {
+    let given = opt.starting_bounds(Bounds { min, max });
+    let (min, max) = match given {
+        Some(bounds) => (bounds.min, bounds.max),
+        None => (min, max),
+    };
+    let (min, max) = match opt.autoframe.as_deref() {
+        Some(mode) => {
+            let search = given.unwrap_or_else(|| Bounds { min, max }.zoomed(0.5));
+            let framed = match autoframe(&generator, search, AUTOFRAME_ITERATIONS) {
+                Some(framed) => framed.with_aspect(RGB_WIDTH, RGB_HEIGHT),
+                None => {
+                    eprintln!("nothing within {} stays close to frame", search);
+                    process::exit(1);
+                }
+            };
+            if mode == "print" {
+                println!("--bounds={}", framed);
+                return;
+            }
+            eprintln!("framed as --bounds={}", framed);
+            (framed.min, framed.max)
+        }
+        None => (min, max),
+    };
+    let Bounds { min, max } = Bounds { min, max }.with_pixel_aspect(opt.pixel_aspect);
+    let palette = || {
+        opt.time("palette", || opt.rgb_palette(opt.palette.clone()))
+            .unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                process::exit(1);
+            })
+    };
+    if opt.dry_run {
+        if let Err(e) = dry_run(&generator, opt, palette, min, max) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    } else if let Some(batch) = &opt.batch {
+        if let Err(e) = render_batch(&generator, opt, batch) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    } else if opt.stats {
+        let palette = palette();
+        let max_iter = opt.max_iter(&palette);
+        let (_, drawn) = draw_file(&generator, opt.power_of_z(), opt, &palette, min, max)
+            .unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                process::exit(1);
+            });
+        let stats =
+            EscapeStats::new(&drawn.escapes, max_iter, STATS_BUCKETS).with_clipped(drawn.clipped);
+        print!("{}", stats);
+    } else if let Some(output) = &opt.output {
+        let palette = palette();
+        let (png, _) = draw_file(&generator, opt.power_of_z(), opt, &palette, min, max)
+            .unwrap_or_else(|e| {
+                eprintln!("couldn't draw {}: {}", output.display(), e);
+                process::exit(1);
+            });
+        if let Err(e) = opt.time("encoding", || png.save(output)) {
+            eprintln!("couldn't save {}: {}", output.display(), e);
+            process::exit(1);
+        }
+        if opt.both {
+            draw_text(&generator, opt, min, max);
+        }
+    } else if opt.image {
+        let mut state = CanvasState::new(min, max, boxed(generator))
+            .with_args(opt.fractal_args())
+            .with_power(opt.power_of_z())
+            .with_rotation(opt.rotate)
+            .with_pixel_aspect(opt.pixel_aspect);
+        // starting on the Mandelbrot set, M goes back to it instead of to a copy of it that
+        // would be rendered again
+        if julia_preview {
+            state = state.with_julia_preview().with_key(VirtualKeyCode::M);
+        } else {
+            let z = opt.mandelbrot_z();
+            state = state
+                .with_fractal(VirtualKeyCode::M, boxed(Mandelbrot::new(Point(z, z))))
+                .with_julia_preview()
+                .with_args(if z != 0.0 {
+                    vec![format!("--mandelbrot={}", z)]
+                } else {
+                    vec![]
+                });
+        }
+        let state = state
+            .with_fractal(VirtualKeyCode::J, boxed(Julia::new(opt.julia_c())))
+            .with_args(vec!["julia".to_owned(), format!("--c={}", opt.julia_c())])
+            .with_fractal(VirtualKeyCode::B, boxed(BurningShip::new()))
+            .with_args(vec!["burning-ship".to_owned()]);
+        let state = match &opt.bookmarks {
+            Some(path) => match load_bookmarks(path) {
+                Ok(bookmarks) => state.with_bookmarks(bookmarks, Some(path.clone())),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    process::exit(1);
+                }
+            },
+            None => state,
+        };
+        let max_iter = opt.max_iter(&palette());
+        // every palette these flags can draw, for O to cycle through from --palette
+        let (names, palettes): (Vec<_>, Vec<_>) = PALETTE_NAMES
+            .iter()
+            .filter_map(|&name| {
+                let palette = RGBPalette::from_name(name).and_then(|p| opt.rgb_palette(p));
+                Some((name, palette.ok()?))
+            })
+            .unzip();
+        let current = names
+            .iter()
+            .position(|&name| name == opt.palette.name())
+            .unwrap();
+        Canvas::new(RGB_WIDTH, RGB_HEIGHT)
+            .title(title)
+            .state(
+                state
+                    .with_command(opt.shared_args(max_iter))
+                    .with_palettes(names, current)
+                    .with_cache_capacity(opt.cache_size)
+                    .with_smooth_coloring(opt.smooth)
+                    .with_export(
+                        opt.export_scale,
+                        opt.ssaa.max(2),
+                        opt.ssaa_pattern,
+                        opt.blending(),
+                    ),
+            )
+            .input(CanvasState::handle_input)
+            .render(zoomable_canvas_render(palettes, max_iter))
+    } else {
+        draw_text(&generator, opt, min, max);
+    }
+    // the terminal view isn't timed
+    if opt.verbose && !opt.timings.0.lock().unwrap().is_empty() {
+        eprint!("{}", opt.timings);
+    }
+}
+
+fn draw_text<G: Generator + Sync>(generator: &G, opt: &Opt, min: Point, max: Point) {
+    let grid = opt.rotated(Grid::new(TERM_WIDTH, TERM_HEIGHT, min, max), min, max);
+    let palette = opt.stretch(&CharPalette);
+    if let Err(e) = draw(
+        generator,
+        &palette,
+        opt.max_iter(&palette),
+        &mut CharRenderer,
+        &grid,
+    ) {
+        eprintln!("couldn't print the view: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run_lyapunov(opt: &Opt, sequence: &str) -> Result<(), String> {
+    if opt.batch.is_some() {
+        return Err("batch rendering isn't supported for Lyapunov fractals".to_owned());
+    }
+    if opt.stats {
+        return Err("Lyapunov fractals don't escape, so there are no stats to print".to_owned());
+    }
+    let lyapunov = Lyapunov::new(sequence)?;
+    let (min, max) = default_view("lyapunov");
+    let draw_text = || {
+        let grid = Grid::new(TERM_WIDTH, TERM_HEIGHT, min, max);
+        let chars = lyapunov::colorize(&lyapunov.sample(&grid), CHAR_DIVERGING);
+        let mut renderer = CharRenderer;
+        renderer.render(chars);
+        renderer
+            .finish()
+            .map_err(|e| format!("couldn't print the view: {}", e))
+    };
+    if opt.image || opt.output.is_some() {
+        let grid = Grid::new(RGB_WIDTH, RGB_HEIGHT, min, max);
+        let mut diverging = RGBPalette::from_name("RdYlBu")?.colors();
+        // stable regions in blue, chaotic ones in red
+        diverging.reverse();
+        let colors = lyapunov::colorize(&lyapunov.sample(&grid), &diverging);
+        if let Some(output) = &opt.output {
+            let mut renderer = PngRenderer::new().with_quality(opt.quality);
+            renderer.render(colors);
+            renderer
+                .save(output)
+                .map_err(|e| format!("couldn't save {}: {}", output.display(), e))?;
+            if opt.both {
+                draw_text()?;
+            }
+            return Ok(());
+        }
+        let mut colors = Some(colors);
+        Canvas::new(RGB_WIDTH, RGB_HEIGHT)
+            .title("Lyapunov")
+            .render(move |_, image| {
+                if let Some(colors) = colors.take() {
+                    RGBRenderer::new(image).render(colors);
+                }
+            })
+    } else {
+        draw_text()?;
+    }
+    Ok(())
+}
+
+fn main() {
+    let matches = Opt::clap().get_matches();
+    let mut opt = Opt::from_clap(&matches);
+    opt.registered = matches
+        .subcommand_name()
+        .and_then(|name| registry::find(name).ok());
+    if let Some(threads) = opt.threads {
+        if let Err(e) = ThreadPoolBuilder::new().num_threads(threads).build_global() {
+            eprintln!("couldn't start {} render threads: {}", threads, e);
+            process::exit(1);
+        }
+    }
+    if let Some(path) = &opt.trap_texture {
+        match TextureTrap::load(path, opt.trap_center, opt.trap_size) {
+            Ok(trap) => opt.texture_trap = Some(trap),
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        }
+    }
+    if let Some(path) = &opt.show_palette {
+        if let Err(e) = save_swatch(&opt, path) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        return;
+    }
+    if let (false, Some(output)) = (opt.compare.is_empty(), &opt.output) {
+        if opt.fractal.is_some() {
+            eprintln!("--compare can't be given with a subcommand");
+            process::exit(1);
+        }
+        if let Err(e) = save_comparison(&opt, &opt.compare, output) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        if opt.verbose {
+            eprint!("{}", opt.timings);
+        }
+        return;
+    }
+    if opt.glynn {
+        return run_as(&opt, &registry::GLYNN, Glynn::new());
+    }
+
+    if let Some(power) = opt.power {
+        return run(
+            &opt,
+            "Power Mandelbrot",
+            PowerMandelbrot::new(power),
+            default_view("--power"),
+            false,
+        );
+    }
+
+    if let Some(preset) = opt.julia_preset {
+        return run(
+            &opt,
+            "Julia",
+            Julia::new(preset.c),
+            (preset.min, preset.max),
+            false,
+        );
+    }
+
+    if opt.random_julia {
+        let seed = opt.seed.unwrap_or_else(rand::random);
+        let c = julia::random_c(&mut StdRng::seed_from_u64(seed));
+        eprintln!("seed {}: c = {}", seed, c);
+        opt.random_c = Some(c);
+        return run_as(&opt, &registry::JULIA, Julia::new(c));
+    }
+
+    if let Some(fractal) = opt.fractal_name {
+        if opt.fractal.is_some() {
+            eprintln!("--fractal can't be given with a subcommand");
+            process::exit(1);
+        }
+        return run_as(&opt, fractal, (fractal.generator)(&Parameters::default()));
+    }
+
+    if let Some(fractal) = opt.registered {
+        return run_as(&opt, fractal, (fractal.generator)(&opt.parameters()));
+    }
+
+    match opt.fractal {
+        #[cfg(feature = "deep-zoom")]
+        Some(FractalOpt::Deep {
+            re,
+            im,
+            radius,
+            perturbation,
+        }) => {
+            let half_width = radius * RGB_WIDTH as f64 / RGB_HEIGHT as f64;
+            let (min, max) = (Point(-half_width, -radius), Point(half_width, radius));
+            if perturbation {
+                // points still going when the reference runs out carry on in full precision
+                let reference_iter = opt.max_iter.unwrap_or(DEEP_REFERENCE_ITERATIONS);
+                let generator = PerturbedMandelbrot::new(re, im, reference_iter);
+                run(&opt, "Deep zoom", generator, (min, max), false)
+            } else {
+                run(
+                    &opt,
+                    "Deep zoom",
+                    DeepMandelbrot::new(re, im),
+                    (min, max),
+                    false,
+                )
+            }
+        }
+        Some(FractalOpt::Lyapunov { ref sequence }) => {
+            if let Err(e) = run_lyapunov(&opt, sequence) {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
         }
+        Some(FractalOpt::List) => print_list(),
+        Some(_) => unreachable!("subcommands named after a fractal are drawn by the registry"),
+        None => run_as(
+            &opt,
+            &registry::MANDELBROT,
+            Mandelbrot::new(Point(opt.mandelbrot_z(), opt.mandelbrot_z())),
+        ),
     }
 }